@@ -0,0 +1,86 @@
+/*
+ * C interface to the europe-elects-csv library, built with `cargo build --release --features ffi`.
+ *
+ * Tables are opaque handles created by ee_poll_table_from_path or ee_poll_table_from_buffer
+ * and released with ee_poll_table_free. Strings returned by the library are NUL-terminated
+ * and owned by their table, so they stay valid until it is freed. Poll 0 is the newest poll.
+ * Panics inside the library never unwind into the caller; they are reported as EE_PANIC.
+ */
+#ifndef EUROPE_ELECTS_CSV_H
+#define EUROPE_ELECTS_CSV_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum EeStatus {
+    EE_OK = 0,
+    EE_NULL_POINTER = 1,
+    EE_INVALID_UTF8 = 2,
+    EE_CSV_ERROR = 3,
+    EE_NOT_CSV = 4,
+    EE_INVALID_PATH = 5,
+    EE_INVALID_JURISDICTION = 6,
+    EE_INDEX_OUT_OF_RANGE = 7,
+    EE_NOT_AVAILABLE = 8,
+    /* An internal error, such as a value the parser could not handle, was caught. The call had no effect. */
+    EE_PANIC = 9
+} EeStatus;
+
+typedef struct EeDate {
+    int32_t year;
+    uint32_t month;
+    uint32_t day;
+} EeDate;
+
+/* A party result. When available is 0, the other fields are meaningless. */
+typedef struct EeValue {
+    double value;
+    uint8_t is_seats;
+    uint8_t available;
+} EeValue;
+
+typedef struct EePollTable EePollTable;
+
+/* Loads a Europe Elects .csv file, whose name gives the jurisdiction, such as "de.csv". */
+EeStatus ee_poll_table_from_path(const char *path, EePollTable **out);
+
+/* Parses len bytes of .csv data for a jurisdiction code, such as "de". */
+EeStatus ee_poll_table_from_buffer(const uint8_t *data, size_t len, const char *jurisdiction, EePollTable **out);
+
+/* Frees a table. Does nothing if table is NULL. */
+void ee_poll_table_free(EePollTable *table);
+
+/* Returns the jurisdiction code of a table, or NULL if table is NULL. */
+const char *ee_poll_table_jurisdiction(const EePollTable *table);
+
+size_t ee_poll_table_poll_count(const EePollTable *table);
+size_t ee_poll_table_party_count(const EePollTable *table);
+
+/* Returns the name of a party column, in alphabetical order, or NULL if out of range. */
+const char *ee_poll_table_party_name(const EePollTable *table, size_t party);
+
+EeStatus ee_poll_polling_firm(const EePollTable *table, size_t poll, const char **out);
+/* Returns EE_NOT_AVAILABLE if the poll has no commissioners. */
+EeStatus ee_poll_commissioners(const EePollTable *table, size_t poll, const char **out);
+EeStatus ee_poll_fieldwork_start(const EePollTable *table, size_t poll, EeDate *out);
+EeStatus ee_poll_fieldwork_end(const EePollTable *table, size_t poll, EeDate *out);
+/* Stores 0 for a national poll and 1 for a European one. */
+EeStatus ee_poll_scope(const EePollTable *table, size_t poll, uint8_t *out);
+/* Returns EE_NOT_AVAILABLE if the poll has no sample size. */
+EeStatus ee_poll_sample_size(const EePollTable *table, size_t poll, double *out);
+/* Gets the result of a party column, numbered as by ee_poll_table_party_name. */
+EeStatus ee_poll_party_value(const EePollTable *table, size_t poll, size_t party, EeValue *out);
+EeStatus ee_poll_other(const EePollTable *table, size_t poll, EeValue *out);
+
+/* Returns a static description of a status code. */
+const char *ee_status_message(int status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* EUROPE_ELECTS_CSV_H */
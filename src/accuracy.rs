@@ -0,0 +1,359 @@
+//! Scoring of polling firms by how close their final polls came to election results.
+use crate::elections::ElectionResult;
+use crate::{PercentageOrSeats, PollOption, PollTable};
+use chrono::Days;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// The lowest and highest weight [FirmRatings::weights] gives a firm.
+const WEIGHT_RANGE: (f32, f32) = (0.25, 4.0);
+
+#[derive(Debug, Clone)]
+/// Settings for [PollTable::score_polls].
+pub struct AccuracyOptions {
+    window_days: u64,
+    final_poll_only: bool,
+}
+
+impl Default for AccuracyOptions {
+    fn default() -> Self {
+        AccuracyOptions::new()
+    }
+}
+
+impl AccuracyOptions {
+    /// Creates options scoring each firm's final poll whose fieldwork ended within 21 days before the election.
+    pub fn new() -> Self {
+        AccuracyOptions {
+            window_days: 21,
+            final_poll_only: true,
+        }
+    }
+
+    /// Sets how many days before the election a poll's fieldwork may have ended.
+    pub fn window_days(mut self, window_days: u64) -> Self {
+        self.window_days = window_days;
+        self
+    }
+
+    /// Sets whether only each firm's last poll in the window is scored, rather than all of them.
+    pub fn final_poll_only(mut self, final_poll_only: bool) -> Self {
+        self.final_poll_only = final_poll_only;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// How close a poll came to an election result. Errors are in percentage points.
+pub struct PollScore {
+    index: usize,
+    polling_firm: String,
+    parties: usize,
+    mean_absolute_error: f32,
+    rmse: f32,
+    lead_error: Option<f32>,
+    log_score: f32,
+}
+
+impl PollScore {
+    /// Returns the index of the poll in its [PollTable].
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the polling firm.
+    pub fn polling_firm(&self) -> &str {
+        &self.polling_firm
+    }
+
+    /// Returns the number of parties compared.
+    pub fn parties(&self) -> usize {
+        self.parties
+    }
+
+    /// Returns the mean absolute error over the parties compared.
+    pub fn mean_absolute_error(&self) -> f32 {
+        self.mean_absolute_error
+    }
+
+    /// Returns the root mean square error over the parties compared.
+    pub fn rmse(&self) -> f32 {
+        self.rmse
+    }
+
+    /// Returns the absolute error on the lead of the winning party over the runner-up,
+    /// or [None] if the poll has no result for either of them. Parties tied in the result are ranked alphabetically.
+    pub fn lead_error(&self) -> Option<f32> {
+        self.lead_error
+    }
+
+    /// Returns the log-likelihood of the election result under the normal distributions implied by the poll's results and sample size,
+    /// per scored party so that polls listing more parties are not penalized. Higher is better.
+    pub fn log_score(&self) -> f32 {
+        self.log_score
+    }
+}
+
+impl PollTable {
+    /// Scores the firms' final polls before an election of the same scope against its result.
+    /// Only parties with a vote share in both the poll and the result are compared.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::accuracy::AccuracyOptions;
+    /// use europe_elects_csv::elections::ElectionHistory;
+    /// let polls = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,Not Available,2021-09-20,2021-09-22,National,1000,Provided,Not Available,1%,26%,23%,51%
+    /// Epic Polling,Not Available,2021-09-10,2021-09-12,National,1000,Provided,Not Available,1%,30%,20%,50%
+    /// Other Polling,Not Available,2021-09-21,2021-09-23,National,1000,Provided,Not Available,1%,22%,26%,52%";
+    /// let results = "Date,Scope,Turnout,First Party,Second Party,Other
+    /// 2021-09-26,National,76.4%,25%,24%,51%";
+    /// let poll_table = PollTable::from_str(polls, "de").unwrap();
+    /// let history = ElectionHistory::from_str(results, "de").unwrap();
+    /// let election = history.latest(&Scope::National).unwrap();
+    /// let scores = poll_table.score_polls(election, &AccuracyOptions::new());
+    ///
+    /// assert_eq!(scores.len(), 2);
+    /// assert_eq!(scores[0].index(), 0);
+    /// assert_eq!(scores[0].mean_absolute_error(), 1.0);
+    /// assert_eq!(scores[0].lead_error(), Some(2.0));
+    /// assert!(scores[0].log_score() > scores[1].log_score());
+    /// ```
+    pub fn score_polls(
+        &self,
+        election: &ElectionResult,
+        options: &AccuracyOptions,
+    ) -> Vec<PollScore> {
+        let date = *election.date();
+        let first_day = date - Days::new(options.window_days);
+        let actual = election.vote_shares();
+
+        let mut candidates: Vec<usize> = self
+            .polls
+            .iter()
+            .enumerate()
+            .filter(|(_, poll)| {
+                poll.scope == *election.scope()
+                    && poll.fieldwork_end <= date
+                    && poll.fieldwork_end >= first_day
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if options.final_poll_only {
+            let mut last: HashMap<String, usize> = HashMap::new();
+            for i in candidates {
+                let poll = &self.polls[i];
+                let entry = last.entry(poll.polling_firm.to_lowercase()).or_insert(i);
+                if self.polls[*entry].fieldwork_end < poll.fieldwork_end {
+                    *entry = i;
+                }
+            }
+            candidates = last.into_values().collect();
+            candidates.sort();
+        }
+
+        let mut ranked: Vec<(&String, f32)> = actual.iter().map(|(p, v)| (p, *v)).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        candidates
+            .into_iter()
+            .filter_map(|i| {
+                let poll = &self.polls[i];
+                let sample_size = poll.effective_sample_size();
+                let shares: HashMap<&String, f32> = poll
+                    .party_results
+                    .iter()
+                    .filter_map(|(party, result)| match result {
+                        PollOption::Some(PercentageOrSeats::Percentage(share)) => {
+                            Some((party, share.value()))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+
+                let mut errors = Vec::new();
+                let mut log_score = 0.0;
+                for (party, result) in &actual {
+                    let Some(polled) = shares.get(party) else {
+                        continue;
+                    };
+                    errors.push(polled - result);
+                    let p = polled.clamp(0.5, 99.5) / 100.0;
+                    let std_dev = (p * (1.0 - p) / sample_size).sqrt() * 100.0;
+                    log_score += -0.5 * ((polled - result) / std_dev).powi(2)
+                        - (std_dev * (2.0 * std::f32::consts::PI).sqrt()).ln();
+                }
+                if errors.is_empty() {
+                    return None;
+                }
+
+                let n = errors.len() as f32;
+                let lead_error = match ranked.as_slice() {
+                    [(winner, first), (runner_up, second), ..] => shares
+                        .get(winner)
+                        .zip(shares.get(runner_up))
+                        .map(|(a, b)| ((a - b) - (first - second)).abs()),
+                    _ => None,
+                };
+                Some(PollScore {
+                    index: i,
+                    polling_firm: poll.polling_firm.clone(),
+                    parties: errors.len(),
+                    mean_absolute_error: errors.iter().map(|e| e.abs()).sum::<f32>() / n,
+                    rmse: (errors.iter().map(|e| e * e).sum::<f32>() / n).sqrt(),
+                    lead_error,
+                    log_score: log_score / n,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A firm's accuracy over all scored polls.
+pub struct FirmRating {
+    polling_firm: String,
+    polls: usize,
+    mean_absolute_error: f32,
+    rmse: f32,
+    lead_error: Option<f32>,
+    log_score: f32,
+}
+
+impl FirmRating {
+    /// Returns the polling firm.
+    pub fn polling_firm(&self) -> &str {
+        &self.polling_firm
+    }
+
+    /// Returns the number of polls scored.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    /// Returns the mean of the polls' mean absolute errors.
+    pub fn mean_absolute_error(&self) -> f32 {
+        self.mean_absolute_error
+    }
+
+    /// Returns the root mean square of the polls' RMSEs.
+    pub fn rmse(&self) -> f32 {
+        self.rmse
+    }
+
+    /// Returns the mean lead error of the polls for which it is known.
+    pub fn lead_error(&self) -> Option<f32> {
+        self.lead_error
+    }
+
+    /// Returns the mean log score of the polls.
+    pub fn log_score(&self) -> f32 {
+        self.log_score
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Poll scores collected across elections and jurisdictions, summarised per firm.
+/// ```
+/// use europe_elects_csv::*;
+/// use europe_elects_csv::accuracy::{AccuracyOptions, FirmRatings};
+/// use europe_elects_csv::average::AverageOptions;
+/// use europe_elects_csv::elections::ElectionHistory;
+/// let polls = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+/// Epic Polling,Not Available,2021-09-20,2021-09-22,National,1000,Provided,Not Available,1%,26%,23%,51%
+/// Other Polling,Not Available,2021-09-21,2021-09-23,National,1000,Provided,Not Available,1%,22%,26%,52%";
+/// let results = "Date,Scope,Turnout,First Party,Second Party,Other
+/// 2021-09-26,National,76.4%,25%,24%,51%";
+/// let poll_table = PollTable::from_str(polls, "de").unwrap();
+/// let history = ElectionHistory::from_str(results, "de").unwrap();
+///
+/// let mut ratings = FirmRatings::new();
+/// for election in history.elections() {
+///     ratings.add(&poll_table, election, &AccuracyOptions::new());
+/// }
+/// let weights = ratings.weights();
+///
+/// assert_eq!(ratings.ratings()[0].polling_firm(), "Epic Polling");
+/// assert!(weights["Epic Polling"] > weights["Other Polling"]);
+/// let options = AverageOptions::new().firm_weights(weights);
+/// ```
+pub struct FirmRatings {
+    scores: Vec<PollScore>,
+}
+
+impl FirmRatings {
+    /// Creates ratings without any scores.
+    pub fn new() -> Self {
+        FirmRatings::default()
+    }
+
+    /// Scores a table's polls against an election and adds the scores.
+    pub fn add(
+        &mut self,
+        poll_table: &PollTable,
+        election: &ElectionResult,
+        options: &AccuracyOptions,
+    ) {
+        self.scores
+            .extend(poll_table.score_polls(election, options));
+    }
+
+    /// Adds scores that were already computed.
+    pub fn extend(&mut self, scores: impl IntoIterator<Item = PollScore>) {
+        self.scores.extend(scores);
+    }
+
+    /// Returns the rating of every firm, from the lowest RMSE to the highest.
+    /// Firms are grouped case-insensitively and named as in their first scored poll.
+    pub fn ratings(&self) -> Vec<FirmRating> {
+        let mut by_firm: BTreeMap<String, Vec<&PollScore>> = BTreeMap::new();
+        for score in &self.scores {
+            by_firm
+                .entry(score.polling_firm.to_lowercase())
+                .or_default()
+                .push(score);
+        }
+
+        let mut ratings: Vec<FirmRating> = by_firm
+            .into_values()
+            .map(|scores| {
+                let n = scores.len() as f32;
+                let lead_errors: Vec<f32> = scores.iter().filter_map(|s| s.lead_error).collect();
+                FirmRating {
+                    polling_firm: scores[0].polling_firm.clone(),
+                    polls: scores.len(),
+                    mean_absolute_error: scores.iter().map(|s| s.mean_absolute_error).sum::<f32>()
+                        / n,
+                    rmse: (scores.iter().map(|s| s.rmse.powi(2)).sum::<f32>() / n).sqrt(),
+                    lead_error: (!lead_errors.is_empty())
+                        .then(|| lead_errors.iter().sum::<f32>() / lead_errors.len() as f32),
+                    log_score: scores.iter().map(|s| s.log_score).sum::<f32>() / n,
+                }
+            })
+            .collect();
+        ratings.sort_by(|a, b| a.rmse.total_cmp(&b.rmse));
+        ratings
+    }
+
+    /// Returns a weight per firm for [crate::average::AverageOptions::firm_weights].
+    ///
+    /// Weights are inversely proportional to each firm's squared RMSE, scaled so that a firm with the pooled RMSE of all firms has a weight of 1,
+    /// and limited to between 0.25 and 4.
+    pub fn weights(&self) -> HashMap<String, f32> {
+        let ratings = self.ratings();
+        if ratings.is_empty() {
+            return HashMap::new();
+        }
+        let pooled = ratings.iter().map(|r| r.rmse.powi(2)).sum::<f32>() / ratings.len() as f32;
+        ratings
+            .into_iter()
+            .map(|rating| {
+                let weight = if rating.rmse > 0.0 {
+                    (pooled / rating.rmse.powi(2)).clamp(WEIGHT_RANGE.0, WEIGHT_RANGE.1)
+                } else {
+                    WEIGHT_RANGE.1
+                };
+                (rating.polling_firm, weight)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,487 @@
+//! Declarative alert rules, such as threshold crossings, lead changes and all-time highs, evaluated against poll tables.
+use crate::average::AverageOptions;
+use crate::collection::PollTableCollection;
+use crate::diff::PollRef;
+use crate::errors::AlertRulesError;
+use crate::identity::PollId;
+use crate::{init_jurisdiction, PercentageOrSeats, Poll, PollOption, PollTable, Scope};
+use chrono::NaiveDate;
+use csv::ReaderBuilder;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+/// The condition an [AlertRule] checks for.
+pub enum Condition {
+    /// A poll has the party below a share, after the previous poll had it at or above it.
+    Below {
+        /// The party column.
+        party: String,
+        /// The share, in percent.
+        threshold: f32,
+    },
+    /// A poll has the party above a share, after the previous poll had it at or below it.
+    Above {
+        /// The party column.
+        party: String,
+        /// The share, in percent.
+        threshold: f32,
+    },
+    /// A poll has the party higher than every earlier poll.
+    AllTimeHigh {
+        /// The party column.
+        party: String,
+    },
+    /// A poll has the party lower than every earlier poll.
+    AllTimeLow {
+        /// The party column.
+        party: String,
+    },
+    /// The party leading the weighted average, as in [PollTable::average_at], changes after a poll.
+    /// The average is of the rule's scope.
+    LeadChange {
+        /// The window of the average, as in [AverageOptions::window_days].
+        window_days: i64,
+    },
+    /// A polling firm publishes its first poll in a number of days.
+    FirmReturns {
+        /// The firm to watch, matched as in [crate::query::PollQuery::firm], or every firm.
+        firm: Option<String>,
+        /// The number of days without a poll.
+        days: i64,
+    },
+    /// A polling firm publishes its first poll in the table.
+    FirstPoll {
+        /// The firm to watch, matched as in [crate::query::PollQuery::firm], or every firm.
+        firm: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A [Condition] checked against the polls of one scope, optionally limited to a jurisdiction.
+///
+/// Polls of different scopes ask different questions, so they are never compared with each other.
+/// ```
+/// use europe_elects_csv::*;
+/// use europe_elects_csv::alerts::{AlertRule, AlertRules, Condition};
+/// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+/// Epic Polling,Not Available,2024-03-06,2024-03-08,European,1000,Provided,Not Available,1%,4%,96%
+/// Epic Polling,Not Available,2024-03-01,2024-03-02,National,1000,Provided,Not Available,1%,6%,94%";
+/// let poll_table = PollTable::from_str(example, "de").unwrap();
+///
+/// let mut rules = AlertRules::new();
+/// rules.add(AlertRule::new(Condition::Below { party: String::from("First Party"), threshold: 5.0 }));
+/// assert!(rules.evaluate(&poll_table, None).is_empty());
+/// ```
+pub struct AlertRule {
+    condition: Condition,
+    jurisdiction: Option<String>,
+    scope: Scope,
+}
+
+impl AlertRule {
+    /// Creates a rule applying to national polls of every jurisdiction.
+    pub fn new(condition: Condition) -> Self {
+        AlertRule {
+            condition,
+            jurisdiction: None,
+            scope: Scope::National,
+        }
+    }
+
+    /// Only applies the rule to a jurisdiction, given by its code such as "de".
+    pub fn jurisdiction(mut self, code: &str) -> Self {
+        self.jurisdiction = Some(code.to_lowercase());
+        self
+    }
+
+    /// Applies the rule to polls of another scope.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Returns the condition of the rule.
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    /// Returns the alerts the rule raises for a table, in chronological order.
+    fn evaluate(&self, index: usize, poll_table: &PollTable) -> Vec<Alert> {
+        let code = poll_table.jurisdiction.code();
+        if self
+            .jurisdiction
+            .as_deref()
+            .is_some_and(|rule| rule != code)
+        {
+            return Vec::new();
+        }
+        // Polls from oldest to newest. Files list the newest poll first, so ties keep that order reversed.
+        let mut polls: Vec<(usize, &Poll)> = poll_table
+            .polls
+            .iter()
+            .enumerate()
+            .filter(|(_, poll)| poll.scope == self.scope)
+            .collect();
+        polls.sort_by_key(|(i, poll)| (poll.fieldwork_end, Reverse(*i)));
+
+        let alert = |date, message, polls: Vec<(usize, &Poll)>| Alert {
+            rule: index,
+            jurisdiction: code,
+            date,
+            message,
+            polls: polls
+                .into_iter()
+                .map(|(i, poll)| PollRef::new(i, poll))
+                .collect(),
+        };
+
+        let mut alerts = Vec::new();
+        match &self.condition {
+            Condition::Below { party, threshold } | Condition::Above { party, threshold } => {
+                let below = matches!(self.condition, Condition::Below { .. });
+                let beyond =
+                    |share: f32| (below && share < *threshold) || (!below && share > *threshold);
+                let mut previous: Option<f32> = None;
+                for (i, poll) in polls {
+                    let Some(share) = share(poll, party) else {
+                        continue;
+                    };
+                    let crossed = previous.is_some_and(|previous| !beyond(previous));
+                    previous = Some(share);
+                    if crossed && beyond(share) {
+                        let message = format!(
+                            "{party} at {share}% in a poll by {}, {} {threshold}%",
+                            poll.polling_firm,
+                            if below { "below" } else { "above" },
+                        );
+                        alerts.push(alert(poll.fieldwork_end, message, vec![(i, poll)]));
+                    }
+                }
+            }
+            Condition::AllTimeHigh { party } | Condition::AllTimeLow { party } => {
+                let high = matches!(self.condition, Condition::AllTimeHigh { .. });
+                let mut record: Option<f32> = None;
+                for (i, poll) in polls {
+                    let Some(share) = share(poll, party) else {
+                        continue;
+                    };
+                    match record {
+                        Some(previous)
+                            if (high && share > previous) || (!high && share < previous) =>
+                        {
+                            let message = format!(
+                                "{party} at {share}% in a poll by {}, a new all-time {} (previously {previous}%)",
+                                poll.polling_firm,
+                                if high { "high" } else { "low" },
+                            );
+                            alerts.push(alert(poll.fieldwork_end, message, vec![(i, poll)]));
+                            record = Some(share);
+                        }
+                        Some(_) => {}
+                        None => record = Some(share),
+                    }
+                }
+            }
+            Condition::LeadChange { window_days } => {
+                let table = PollTable::new(
+                    polls.iter().map(|(_, poll)| (*poll).clone()).collect(),
+                    poll_table.jurisdiction,
+                );
+                let options = AverageOptions::new()
+                    .window_days(*window_days)
+                    .scope(self.scope);
+                let mut dates: Vec<NaiveDate> =
+                    polls.iter().map(|(_, poll)| poll.fieldwork_end).collect();
+                dates.dedup();
+                let mut previous: Option<(String, f32)> = None;
+                for date in dates {
+                    let average = table.average_at(&date, &options);
+                    let leader = average
+                        .parties()
+                        .iter()
+                        .map(|(party, average)| (party.clone(), average.value()))
+                        .max_by(|a, b| a.1.total_cmp(&b.1));
+                    let Some(leader) = leader else {
+                        continue;
+                    };
+                    if let Some((previous_leader, _)) = &previous {
+                        if *previous_leader != leader.0 {
+                            let behind = average
+                                .party(previous_leader)
+                                .map_or(0.0, |average| average.value());
+                            let message = format!(
+                                "{} overtook {previous_leader} in the average, {:.1}% to {behind:.1}%",
+                                leader.0, leader.1,
+                            );
+                            let triggering = polls
+                                .iter()
+                                .filter(|(_, poll)| poll.fieldwork_end == date)
+                                .copied()
+                                .collect();
+                            alerts.push(alert(date, message, triggering));
+                        }
+                    }
+                    previous = Some(leader);
+                }
+            }
+            Condition::FirmReturns { firm, days } => {
+                let firm = firm.as_ref().map(|firm| firm.to_lowercase());
+                let mut last_poll: HashMap<String, NaiveDate> = HashMap::new();
+                for (i, poll) in polls {
+                    let name = poll.polling_firm.to_lowercase();
+                    if firm.as_ref().is_some_and(|firm| !name.contains(firm)) {
+                        continue;
+                    }
+                    if let Some(last) = last_poll.insert(name, poll.fieldwork_end) {
+                        let gap = (poll.fieldwork_end - last).num_days();
+                        if gap >= *days {
+                            let message =
+                                format!("First poll by {} in {gap} days", poll.polling_firm);
+                            alerts.push(alert(poll.fieldwork_end, message, vec![(i, poll)]));
+                        }
+                    }
+                }
+            }
+            Condition::FirstPoll { firm } => {
+                let firm = firm.as_ref().map(|firm| firm.to_lowercase());
+                let mut seen: HashSet<String> = HashSet::new();
+                for (i, poll) in polls {
+                    let name = poll.polling_firm.to_lowercase();
+                    if firm.as_ref().is_some_and(|firm| !name.contains(firm)) {
+                        continue;
+                    }
+                    if seen.insert(name) {
+                        let message = format!("First poll by {}", poll.polling_firm);
+                        alerts.push(alert(poll.fieldwork_end, message, vec![(i, poll)]));
+                    }
+                }
+            }
+        }
+        alerts
+    }
+}
+
+/// Returns the share of a party in a poll, if it is given as a percentage.
+fn share(poll: &Poll, party: &str) -> Option<f32> {
+    match poll.party_results.get(party) {
+        Some(PollOption::Some(PercentageOrSeats::Percentage(share))) => Some(share.value()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A rule being triggered, as returned by [AlertRules::evaluate].
+pub struct Alert {
+    /// The position of the triggered rule in its [AlertRules].
+    pub rule: usize,
+    /// The code of the jurisdiction, such as "de".
+    pub jurisdiction: &'static str,
+    /// The end of fieldwork of the triggering polls.
+    pub date: NaiveDate,
+    /// A description of what happened.
+    pub message: String,
+    /// The polls that triggered the rule.
+    pub polls: Vec<PollRef>,
+}
+
+impl Alert {
+    /// Returns whether a poll is among those that triggered the alert.
+    pub fn involves(&self, id: &PollId) -> bool {
+        self.polls.iter().any(|poll| poll.id == *id)
+    }
+
+    /// Returns the alert as a single line of JSON.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("Alert should serialize to JSON")
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.jurisdiction, self.date, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A list of [AlertRule]s.
+pub struct AlertRules {
+    rules: Vec<AlertRule>,
+}
+
+impl AlertRules {
+    /// Creates an empty list of rules.
+    pub fn new() -> Self {
+        AlertRules::default()
+    }
+
+    /// Adds a rule to the end of the list.
+    pub fn add(&mut self, rule: AlertRule) {
+        self.rules.push(rule);
+    }
+
+    /// Returns the rules, in order.
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// As with [AlertRules::from_str], but reads the .csv data from a file.
+    pub fn try_from_path(path: &str) -> Result<AlertRules, AlertRulesError> {
+        let s = std::fs::read_to_string(path)?;
+        AlertRules::from_str(&s)
+    }
+
+    /// Evaluates every rule against a table, returning the alerts in chronological order.
+    ///
+    /// Earlier polls are always taken into account, for instance to find all-time highs, but only alerts dated on or after `since` are returned.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::alerts::{AlertRule, AlertRules, Condition};
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,26%,33%,41%
+    /// Other Polling,Not Available,2024-03-01,2024-03-02,National,1000,Provided,Not Available,1%,29%,31%,40%
+    /// Epic Polling,Not Available,2024-02-06,2024-02-08,National,1000,Provided,Not Available,1%,32%,28%,40%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    ///
+    /// let mut rules = AlertRules::new();
+    /// rules.add(AlertRule::new(Condition::AllTimeHigh { party: String::from("Second Party") }));
+    /// rules.add(AlertRule::new(Condition::LeadChange { window_days: 28 }));
+    /// rules.add(AlertRule::new(Condition::Below { party: String::from("First Party"), threshold: 30.0 }));
+    /// let alerts = rules.evaluate(&poll_table, None);
+    ///
+    /// assert_eq!(alerts.len(), 4);
+    /// assert_eq!(alerts[0].message, "Second Party at 31% in a poll by Other Polling, a new all-time high (previously 28%)");
+    /// assert_eq!(alerts[1].rule, 1);
+    /// assert_eq!(alerts[1].message, "Second Party overtook First Party in the average, 30.5% to 29.5%");
+    /// // Only the poll crossing the threshold raises an alert, not the one after it.
+    /// let below: Vec<_> = alerts.iter().filter(|alert| alert.rule == 2).collect();
+    /// assert_eq!(below.len(), 1);
+    /// assert_eq!(below[0].message, "First Party at 29% in a poll by Other Polling, below 30%");
+    /// ```
+    pub fn evaluate(&self, poll_table: &PollTable, since: Option<NaiveDate>) -> Vec<Alert> {
+        let mut alerts: Vec<Alert> = self
+            .rules
+            .iter()
+            .enumerate()
+            .flat_map(|(i, rule)| rule.evaluate(i, poll_table))
+            .filter(|alert| since.is_none_or(|since| alert.date >= since))
+            .collect();
+        alerts.sort_by_key(|alert| alert.date);
+        alerts
+    }
+
+    /// As with [AlertRules::evaluate], for every table of a collection in turn.
+    pub fn evaluate_collection(
+        &self,
+        collection: &PollTableCollection,
+        since: Option<NaiveDate>,
+    ) -> Vec<Alert> {
+        collection
+            .tables()
+            .iter()
+            .flat_map(|table| self.evaluate(table, since))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleRecord {
+    #[serde(rename = "Rule")]
+    rule: String,
+    #[serde(rename = "Jurisdiction", default)]
+    jurisdiction: Option<String>,
+    #[serde(rename = "Scope", default)]
+    scope: Option<Scope>,
+    #[serde(rename = "Party", default)]
+    party: Option<String>,
+    #[serde(rename = "Firm", default)]
+    firm: Option<String>,
+    #[serde(rename = "Value", default)]
+    value: Option<f32>,
+}
+
+impl FromStr for AlertRules {
+    type Err = AlertRulesError;
+
+    /// Reads rules from .csv data with a "Rule" column and optional "Jurisdiction", "Scope", "Party", "Firm" and "Value" columns.
+    /// Rules without a scope apply to national polls.
+    ///
+    /// | Rule | Needs | Value |
+    /// | --- | --- | --- |
+    /// | below, above | Party | Threshold in percent |
+    /// | all-time-high, all-time-low | Party | |
+    /// | lead-change | | Average window in days, 28 by default |
+    /// | firm-returns | | Days without a poll, 90 by default |
+    /// | first-poll | | |
+    /// ```
+    /// use europe_elects_csv::alerts::{AlertRules, Condition};
+    /// use std::str::FromStr;
+    /// let example = "Rule,Jurisdiction,Scope,Party,Firm,Value
+    /// below,de,National,FDP,,5
+    /// firm-returns,,,,Forsa,";
+    /// let rules = AlertRules::from_str(example).unwrap();
+    ///
+    /// assert_eq!(rules.rules()[0].condition(), &Condition::Below { party: String::from("FDP"), threshold: 5.0 });
+    /// assert_eq!(rules.rules()[1].condition(), &Condition::FirmReturns { firm: Some(String::from("Forsa")), days: 90 });
+    /// ```
+    fn from_str(s: &str) -> Result<AlertRules, AlertRulesError> {
+        let mut rdr = ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(s.as_bytes());
+        let mut rules = AlertRules::new();
+
+        for result in rdr.deserialize() {
+            let record: RuleRecord = result?;
+            let party = || {
+                record
+                    .party
+                    .clone()
+                    .ok_or_else(|| AlertRulesError::MissingPartyError(record.rule.clone()))
+            };
+            let threshold = || {
+                record
+                    .value
+                    .ok_or_else(|| AlertRulesError::MissingValueError(record.rule.clone()))
+            };
+            let condition = match record.rule.to_lowercase().as_str() {
+                "below" => Condition::Below {
+                    party: party()?,
+                    threshold: threshold()?,
+                },
+                "above" => Condition::Above {
+                    party: party()?,
+                    threshold: threshold()?,
+                },
+                "all-time-high" => Condition::AllTimeHigh { party: party()? },
+                "all-time-low" => Condition::AllTimeLow { party: party()? },
+                "lead-change" => Condition::LeadChange {
+                    window_days: record.value.map_or(28, |value| value as i64),
+                },
+                "firm-returns" => Condition::FirmReturns {
+                    firm: record.firm.clone(),
+                    days: record.value.map_or(90, |value| value as i64),
+                },
+                "first-poll" => Condition::FirstPoll {
+                    firm: record.firm.clone(),
+                },
+                _ => return Err(AlertRulesError::UnknownRuleError(record.rule)),
+            };
+
+            let mut rule = AlertRule::new(condition);
+            if let Some(code) = &record.jurisdiction {
+                if !init_jurisdiction().contains_key(&code.to_lowercase()) {
+                    return Err(AlertRulesError::InvalidJurisdictionError(code.clone()));
+                }
+                rule = rule.jurisdiction(code);
+            }
+            if let Some(scope) = record.scope {
+                rule = rule.scope(scope);
+            }
+            rules.add(rule);
+        }
+        Ok(rules)
+    }
+}
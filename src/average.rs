@@ -0,0 +1,241 @@
+//! Rolling averages of poll results.
+use crate::simulation::VoteShareEstimate;
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable, Scope};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone)]
+/// Settings for [PollTable::average_at].
+pub struct AverageOptions {
+    window_days: i64,
+    centered: bool,
+    firm_weights: HashMap<String, f32>,
+    scope: Scope,
+}
+
+impl Default for AverageOptions {
+    fn default() -> Self {
+        AverageOptions::new()
+    }
+}
+
+impl AverageOptions {
+    /// Creates options for a trailing average of national polls over a window of 28 days.
+    pub fn new() -> Self {
+        AverageOptions {
+            window_days: 28,
+            centered: false,
+            firm_weights: HashMap::new(),
+            scope: Scope::National,
+        }
+    }
+
+    /// Sets how many days before (and, for centered averages, after) the date polls are included.
+    pub fn window_days(mut self, window_days: i64) -> Self {
+        self.window_days = window_days;
+        self
+    }
+
+    /// Sets whether polls after the date are included as well. Trailing averages only use polls whose fieldwork has ended by the date.
+    pub fn centered(mut self, centered: bool) -> Self {
+        self.centered = centered;
+        self
+    }
+
+    /// Sets a weight per polling firm, such as [crate::accuracy::FirmRatings::weights], by which each firm's polls are multiplied.
+    /// Firm names are compared case-insensitively, and firms without a weight have a weight of 1.
+    pub fn firm_weights(mut self, firm_weights: HashMap<String, f32>) -> Self {
+        self.firm_weights = firm_weights
+            .into_iter()
+            .map(|(firm, weight)| (firm.to_lowercase(), weight))
+            .collect();
+        self
+    }
+
+    /// Sets the scope of the polls averaged, as national and European Parliament polls ask different questions.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::average::AverageOptions;
+    /// use chrono::NaiveDate;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,20%,80%
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,European,1000,Provided,Not Available,1%,30%,70%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    ///
+    /// let national = poll_table.average_at(&date, &AverageOptions::new());
+    /// let european = poll_table.average_at(&date, &AverageOptions::new().scope(Scope::European));
+    /// assert_eq!(national.value("First Party"), Some(20.0));
+    /// assert_eq!(european.value("First Party"), Some(30.0));
+    /// ```
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Returns the weight of a poll in the average at a date, or [None] if the poll is outside the window or of another scope.
+    ///
+    /// Polls are weighted by the square root of their sample size and their firm's weight,
+    /// and linearly less the further their fieldwork midpoint is from the date.
+    pub(crate) fn weight(&self, poll: &Poll, date: &NaiveDate) -> Option<f32> {
+        if poll.scope != self.scope || (!self.centered && poll.fieldwork_end > *date) {
+            return None;
+        }
+        let age = (*date - poll.fieldwork_midpoint()).num_days().abs();
+        if age > self.window_days {
+            return None;
+        }
+        let decay = 1.0 - age as f32 / (self.window_days + 1) as f32;
+        let firm_weight = self
+            .firm_weights
+            .get(&poll.polling_firm.to_lowercase())
+            .copied()
+            .unwrap_or(1.0);
+        Some(poll.effective_sample_size().sqrt() * decay * firm_weight)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+/// The average result of a party.
+pub struct PartyAverage {
+    value: f32,
+    std_dev: f32,
+    polls: usize,
+}
+
+impl PartyAverage {
+    pub(crate) fn new(value: f32, std_dev: f32, polls: usize) -> Self {
+        PartyAverage {
+            value,
+            std_dev,
+            polls,
+        }
+    }
+
+    /// Returns the weighted average result, in percent.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Returns the weighted standard deviation of the polls around the average, in percentage points.
+    pub fn std_dev(&self) -> f32 {
+        self.std_dev
+    }
+
+    /// Returns the number of polls with a result for the party.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The average of polls at a date, as returned by [PollTable::average_at].
+pub struct PollAverage {
+    date: NaiveDate,
+    parties: BTreeMap<String, PartyAverage>,
+}
+
+impl PollAverage {
+    pub(crate) fn new(date: NaiveDate, parties: BTreeMap<String, PartyAverage>) -> Self {
+        PollAverage { date, parties }
+    }
+
+    /// Returns the date of the average.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// Returns the average of every party with at least one poll, in alphabetical order.
+    pub fn parties(&self) -> &BTreeMap<String, PartyAverage> {
+        &self.parties
+    }
+
+    /// Returns the average of a party.
+    pub fn party(&self, party: &str) -> Option<&PartyAverage> {
+        self.parties.get(party)
+    }
+
+    /// Returns the average result of a party, in percent.
+    pub fn value(&self, party: &str) -> Option<f32> {
+        self.party(party).map(PartyAverage::value)
+    }
+
+    /// Returns whether no poll was included in the average.
+    pub fn is_empty(&self) -> bool {
+        self.parties.is_empty()
+    }
+
+    /// Converts the average to a [VoteShareEstimate], using the spread of the polls as the uncertainty.
+    pub fn to_estimate(&self) -> VoteShareEstimate {
+        let mut estimate = VoteShareEstimate::new();
+        for (party, average) in &self.parties {
+            estimate.insert(party, average.value, average.std_dev);
+        }
+        estimate
+    }
+}
+
+impl PollTable {
+    /// Returns the weighted average of the polls around a date. Only results given as percentages are averaged.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::average::AverageOptions;
+    /// use chrono::NaiveDate;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,20%,30%,50%
+    /// Other Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,22%,30%,48%
+    /// Late Polling,Not Available,2024-03-20,2024-03-22,National,1000,Provided,Not Available,1%,40%,30%,30%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    /// let average = poll_table.average_at(&date, &AverageOptions::new());
+    ///
+    /// assert!((average.value("First Party").unwrap() - 21.0).abs() < 0.01);
+    /// assert_eq!(average.party("Second Party").unwrap().polls(), 2);
+    /// ```
+    pub fn average_at(&self, date: &NaiveDate, options: &AverageOptions) -> PollAverage {
+        self.average_excluding(date, options, None)
+    }
+
+    /// Returns the weighted average of the polls around a date, leaving out the poll at an index.
+    pub(crate) fn average_excluding(
+        &self,
+        date: &NaiveDate,
+        options: &AverageOptions,
+        exclude: Option<usize>,
+    ) -> PollAverage {
+        let mut results: BTreeMap<&String, Vec<(f32, f32)>> = BTreeMap::new();
+        for (i, poll) in self.polls.iter().enumerate() {
+            if Some(i) == exclude || poll.scope != options.scope {
+                continue;
+            }
+            let Some(weight) = options.weight(poll, date) else {
+                continue;
+            };
+            for (party, result) in &poll.party_results {
+                if let PollOption::Some(PercentageOrSeats::Percentage(share)) = result {
+                    results
+                        .entry(party)
+                        .or_default()
+                        .push((share.value(), weight));
+                }
+            }
+        }
+
+        let parties = results
+            .into_iter()
+            .map(|(party, values)| {
+                let total: f32 = values.iter().map(|(_, weight)| weight).sum();
+                let value = values.iter().map(|(x, weight)| x * weight).sum::<f32>() / total;
+                let variance = values
+                    .iter()
+                    .map(|(x, weight)| weight * (x - value).powi(2))
+                    .sum::<f32>()
+                    / total;
+                let average = PartyAverage::new(value, variance.sqrt(), values.len());
+                (party.clone(), average)
+            })
+            .collect();
+        PollAverage::new(*date, parties)
+    }
+}
@@ -0,0 +1,23 @@
+//! Serves a directory of Europe Elects .csv files over a local HTTP API, see [europe_elects_csv::server].
+use clap::Parser;
+use europe_elects_csv::server::PollServer;
+use std::error::Error;
+
+#[derive(Parser)]
+#[command(version)]
+/// Serves a directory of Europe Elects .csv files over a local HTTP API.
+struct Cli {
+    /// Directory of .csv files to serve.
+    dir: String,
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let server = PollServer::new(&cli.dir)?;
+    eprintln!("Serving {} on http://{}", cli.dir, cli.addr);
+    server.serve(&cli.addr)?;
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Aggregation of party columns into blocs, alliances and coalitions.
+use crate::errors::BlocMapError;
+use crate::{Percentage, PercentageOrSeats, Poll, PollOption, PollTable, Seats};
+use chrono::NaiveDate;
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Deserialize)]
+struct BlocAssignment {
+    #[serde(rename = "Party")]
+    party: String,
+    #[serde(rename = "Bloc")]
+    bloc: String,
+    #[serde(rename = "From", default)]
+    from: Option<NaiveDate>,
+    #[serde(rename = "Until", default)]
+    until: Option<NaiveDate>,
+}
+
+impl BlocAssignment {
+    fn applies(&self, party: &str, date: &NaiveDate) -> bool {
+        self.party == party
+            && self.from.is_none_or(|from| from <= *date)
+            && self.until.is_none_or(|until| *date <= until)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Maps party columns to blocs, optionally only for a period of time.
+///
+/// Applying a [BlocMap] to a [Poll] replaces the columns of every assigned party with one column per bloc.
+/// Parties without an assignment keep their own column, and the "Other" column is left unchanged.
+pub struct BlocMap {
+    assignments: Vec<BlocAssignment>,
+}
+
+impl BlocMap {
+    /// Creates an empty [BlocMap].
+    pub fn new() -> Self {
+        BlocMap::default()
+    }
+
+    /// Assigns a party to a bloc for all polls.
+    pub fn assign(&mut self, party: &str, bloc: &str) {
+        self.assign_between(party, bloc, None, None);
+    }
+
+    /// Assigns a party to a bloc for polls whose fieldwork ended between two dates, both inclusive.
+    /// Where several assignments of one party overlap, the first one made is used.
+    pub fn assign_between(
+        &mut self,
+        party: &str,
+        bloc: &str,
+        from: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) {
+        self.assignments.push(BlocAssignment {
+            party: party.to_string(),
+            bloc: bloc.to_string(),
+            from,
+            until,
+        });
+    }
+
+    /// Returns the bloc of a party on a given date, if it has one.
+    pub fn bloc_of(&self, party: &str, date: &NaiveDate) -> Option<&str> {
+        self.assignments
+            .iter()
+            .find(|assignment| assignment.applies(party, date))
+            .map(|assignment| assignment.bloc.as_str())
+    }
+
+    /// As with [BlocMap::from_str], but reads the .csv data from a file.
+    pub fn try_from_path(path: &str) -> Result<BlocMap, BlocMapError> {
+        let s = std::fs::read_to_string(path)?;
+        BlocMap::from_str(&s)
+    }
+
+    /// Returns a copy of the poll with the results of each bloc's members summed into one column per bloc.
+    ///
+    /// Members that are not available are left out of the sum, and a bloc is only not available if all of its members are.
+    /// Blocs take the kind of result of their members, so seat-based polls produce seat-based blocs.
+    /// A bloc named like a party column that is not in any bloc is an error, as their results would overwrite each other.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::blocs::BlocMap;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,PSOE,Sumar,PP,Vox,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,28%,Not Available,34%,10%,28%";
+    /// let poll_table = PollTable::from_str(example, "es").unwrap();
+    ///
+    /// let mut blocs = BlocMap::new();
+    /// blocs.assign("PSOE", "Left");
+    /// blocs.assign("Sumar", "Left");
+    /// blocs.assign("PP", "Right");
+    /// blocs.assign("Vox", "Right");
+    /// let bloc_poll = blocs.apply_to_poll(poll_table.poll_by_index(0).unwrap()).unwrap();
+    ///
+    /// assert_eq!(bloc_poll.party_results()["Left"].poll_unwrap().value(), 28.0);
+    /// assert_eq!(bloc_poll.party_results()["Right"].poll_unwrap().value(), 44.0);
+    /// assert!(!bloc_poll.party_results().contains_key("PSOE"));
+    ///
+    /// let mut clashing = BlocMap::new();
+    /// clashing.assign("Sumar", "PSOE");
+    /// assert!(clashing.apply_to_poll(poll_table.poll_by_index(0).unwrap()).is_err());
+    /// ```
+    pub fn apply_to_poll(&self, poll: &Poll) -> Result<Poll, BlocMapError> {
+        let mut blocs: HashMap<String, PollOption<PercentageOrSeats>> = HashMap::new();
+        let mut party_results = HashMap::new();
+
+        for (party, result) in &poll.party_results {
+            let Some(bloc) = self.bloc_of(party, &poll.fieldwork_end) else {
+                party_results.insert(party.clone(), *result);
+                continue;
+            };
+            let total = blocs
+                .entry(bloc.to_string())
+                .or_insert(PollOption::NotAvailable);
+            *total = match (*total, *result) {
+                (PollOption::NotAvailable, result) => result,
+                (total, PollOption::NotAvailable) => total,
+                (PollOption::Some(total), PollOption::Some(result)) => {
+                    PollOption::Some(add(total, result))
+                }
+            };
+        }
+        if let Some(bloc) = blocs.keys().find(|bloc| party_results.contains_key(*bloc)) {
+            return Err(BlocMapError::NameCollisionError(bloc.clone()));
+        }
+        party_results.extend(blocs);
+
+        Ok(Poll {
+            party_results,
+            ..poll.clone()
+        })
+    }
+
+    /// Applies [BlocMap::apply_to_poll] to every poll in a [PollTable].
+    pub fn apply(&self, poll_table: &PollTable) -> Result<PollTable, BlocMapError> {
+        Ok(PollTable::new(
+            poll_table
+                .polls
+                .iter()
+                .map(|poll| self.apply_to_poll(poll))
+                .collect::<Result<_, _>>()?,
+            poll_table.jurisdiction,
+        ))
+    }
+}
+
+fn add(total: PercentageOrSeats, result: PercentageOrSeats) -> PercentageOrSeats {
+    match total {
+        PercentageOrSeats::Percentage(_) => {
+            PercentageOrSeats::Percentage(Percentage(total.value() + result.value()))
+        }
+        PercentageOrSeats::Seats(_) => {
+            PercentageOrSeats::Seats(Seats(total.value() + result.value()))
+        }
+    }
+}
+
+impl FromStr for BlocMap {
+    type Err = BlocMapError;
+
+    /// Reads a bloc map from .csv data with "Party" and "Bloc" columns, and optional "From" and "Until" date columns.
+    /// ```
+    /// use europe_elects_csv::blocs::BlocMap;
+    /// use chrono::NaiveDate;
+    /// use std::str::FromStr;
+    /// let example = "Party,Bloc,From,Until
+    /// Possible Party,Left,,2023-05-31
+    /// Possible Party,Left Alliance,2023-06-01,";
+    /// let blocs = BlocMap::from_str(example).unwrap();
+    ///
+    /// let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    /// assert_eq!(blocs.bloc_of("Possible Party", &date), Some("Left Alliance"));
+    /// ```
+    fn from_str(s: &str) -> Result<BlocMap, BlocMapError> {
+        let mut rdr = ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(s.as_bytes());
+        let mut assignments = Vec::new();
+
+        for result in rdr.deserialize() {
+            let record: BlocAssignment = result?;
+            assignments.push(record);
+        }
+
+        Ok(BlocMap { assignments })
+    }
+}
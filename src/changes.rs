@@ -0,0 +1,181 @@
+//! Poll-to-poll changes of party results by polling firm.
+use crate::{PercentageOrSeats, PollOption, PollTable};
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+/// The result of one party in a poll and in the same firm's previous poll.
+pub struct PartyChange {
+    party: String,
+    current: Option<PollOption<PercentageOrSeats>>,
+    previous: Option<PollOption<PercentageOrSeats>>,
+}
+
+impl PartyChange {
+    /// Returns the party column name.
+    pub fn party(&self) -> &str {
+        &self.party
+    }
+
+    /// Returns the party's result in the poll, or None if the poll has no column for the party.
+    pub fn current(&self) -> Option<&PollOption<PercentageOrSeats>> {
+        self.current.as_ref()
+    }
+
+    /// Returns the party's result in the previous poll, or None if the previous poll has no column for the party.
+    pub fn previous(&self) -> Option<&PollOption<PercentageOrSeats>> {
+        self.previous.as_ref()
+    }
+
+    /// Returns the change in percentage points or seats, if the party has a result of the same kind in both polls.
+    pub fn change(&self) -> Option<f32> {
+        match (self.current?, self.previous?) {
+            (
+                PollOption::Some(PercentageOrSeats::Percentage(current)),
+                PollOption::Some(PercentageOrSeats::Percentage(previous)),
+            ) => Some(current.value() - previous.value()),
+            (
+                PollOption::Some(PercentageOrSeats::Seats(current)),
+                PollOption::Some(PercentageOrSeats::Seats(previous)),
+            ) => Some(current.value() - previous.value()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The changes between a poll and the previous poll by the same firm with the same scope.
+pub struct PollChange {
+    index: usize,
+    previous_index: usize,
+    days_elapsed: i64,
+    parties: Vec<PartyChange>,
+}
+
+impl PollChange {
+    /// Returns the index of the poll in its [PollTable].
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the index of the previous poll in its [PollTable].
+    pub fn previous_index(&self) -> usize {
+        self.previous_index
+    }
+
+    /// Returns the number of days between the end of the previous poll's fieldwork and the end of this poll's fieldwork.
+    pub fn days_elapsed(&self) -> i64 {
+        self.days_elapsed
+    }
+
+    /// Returns the change of every party in either poll, in alphabetical order.
+    pub fn parties(&self) -> &[PartyChange] {
+        &self.parties
+    }
+
+    /// Returns the change of one party.
+    pub fn party(&self, party: &str) -> Option<&PartyChange> {
+        self.parties.iter().find(|change| change.party == party)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A party whose result changed between two polls by the same firm, as returned by [PollTable::biggest_movers].
+pub struct Mover {
+    /// The index of the poll in its [PollTable].
+    pub index: usize,
+    /// The party column name.
+    pub party: String,
+    /// The change in percentage points or seats since the firm's previous poll.
+    pub change: f32,
+}
+
+impl PollTable {
+    /// Returns the changes between the poll at the given index and the same firm's previous poll of the same scope,
+    /// or None if there is no such poll.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Third Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,60%,30%,5%,5%
+    /// Epic Polling,The Daily Snail,2024-02-06,2024-02-08,National,2054,Provided,Not Available,1%,58%,32,Not Available,10%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let changes = poll_table.changes(0).unwrap();
+    ///
+    /// assert_eq!(changes.days_elapsed(), 29);
+    /// assert_eq!(changes.party("First Party").unwrap().change(), Some(2.0));
+    /// assert_eq!(changes.party("Third Party").unwrap().change(), None);
+    /// // Seats and percentages are not compared.
+    /// assert_eq!(changes.party("Second Party").unwrap().change(), None);
+    /// ```
+    pub fn changes(&self, index: usize) -> Option<PollChange> {
+        let previous_index = self.previous_poll(index)?;
+        let poll = &self.polls[index];
+        let previous = &self.polls[previous_index];
+
+        let parties: BTreeSet<&String> = poll
+            .party_results
+            .keys()
+            .chain(previous.party_results.keys())
+            .collect();
+
+        Some(PollChange {
+            index,
+            previous_index,
+            days_elapsed: (poll.fieldwork_end - previous.fieldwork_end).num_days(),
+            parties: parties
+                .into_iter()
+                .map(|party| PartyChange {
+                    party: party.clone(),
+                    current: poll.party_results.get(party).copied(),
+                    previous: previous.party_results.get(party).copied(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the largest changes of any party since the firm's previous poll, among polls whose fieldwork ended
+    /// between two dates, both inclusive. Movers are sorted from the largest to the smallest absolute change.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use chrono::NaiveDate;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,60%,30%,10%
+    /// Other Polling,The Daily Snail,2024-03-01,2024-03-02,National,2054,Provided,Not Available,1%,50%,45%,5%
+    /// Other Polling,The Daily Snail,2024-02-01,2024-02-02,National,2054,Provided,Not Available,1%,55%,40%,5%
+    /// Epic Polling,The Daily Snail,2024-02-06,2024-02-08,National,2054,Provided,Not Available,1%,58%,32%,10%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    ///
+    /// let from = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    /// let until = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+    /// let movers = poll_table.biggest_movers(&from, &until, 2);
+    ///
+    /// assert_eq!(movers[0].index, 1);
+    /// assert_eq!(movers[0].change.abs(), 5.0);
+    /// assert_eq!(movers.len(), 2);
+    /// ```
+    pub fn biggest_movers(&self, from: &NaiveDate, until: &NaiveDate, limit: usize) -> Vec<Mover> {
+        let mut movers: Vec<Mover> = (0..self.polls.len())
+            .filter(|i| (from..=until).contains(&&self.polls[*i].fieldwork_end))
+            .filter_map(|i| self.changes(i))
+            .flat_map(|changes| {
+                let index = changes.index;
+                changes.parties.into_iter().filter_map(move |party| {
+                    Some(Mover {
+                        index,
+                        change: party.change()?,
+                        party: party.party,
+                    })
+                })
+            })
+            .collect();
+        movers.sort_by(|a, b| {
+            b.change
+                .abs()
+                .total_cmp(&a.change.abs())
+                .then(a.index.cmp(&b.index))
+                .then_with(|| a.party.cmp(&b.party))
+        });
+        movers.truncate(limit);
+        movers
+    }
+}
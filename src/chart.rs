@@ -0,0 +1,394 @@
+//! Opinion polling charts, rendered as SVG or PNG without any network access.
+use crate::average::AverageOptions;
+use crate::elections::ElectionHistory;
+use crate::errors::ChartError;
+use crate::party::PartyMetadata;
+use crate::{PercentageOrSeats, PollOption, PollTable, Scope};
+use chrono::{Datelike, Months, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Colors for parties without a color in their metadata.
+const PALETTE: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+    "#bcbd22", "#17becf",
+];
+
+/// Space around the plot area, in pixels: left, right (for the legend), top and bottom.
+const MARGINS: (f32, f32, f32, f32) = (50.0, 170.0, 50.0, 40.0);
+
+/// A point on an average line: the date, the average and the standard deviation of the polls around it.
+type AveragePoint = (NaiveDate, f32, f32);
+
+/// The most dates at which the average line is evaluated.
+const AVERAGE_POINTS: i64 = 240;
+
+/// A chart of a [PollTable]: one dot per poll per party at the fieldwork midpoint,
+/// and a centered rolling average with a band of one standard deviation of the polls around it.
+/// Only results given as percentages, in polls of the chart's scope, are drawn.
+/// ```
+/// use europe_elects_csv::*;
+/// use europe_elects_csv::chart::Chart;
+/// use chrono::NaiveDate;
+/// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Third Party,Other
+/// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,20%,Not Available,50%
+/// Epic Polling,Not Available,2024-03-06,2024-03-08,European,1000,Provided,Not Available,1%,25%,20%,10%,45%
+/// Other Polling,Not Available,2024-01-06,2024-01-08,National,1000,Provided,Not Available,1%,28%,22%,Not Available,50%";
+/// let poll_table = PollTable::from_str(example, "de").unwrap();
+/// let svg = Chart::new(&poll_table)
+///     .threshold(5.0)
+///     .election(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(), "Election")
+///     .to_svg();
+///
+/// assert!(svg.starts_with("<svg"));
+/// assert!(svg.contains("First Party"));
+/// assert!(!svg.contains("Third Party"));
+/// assert!(Chart::new(&poll_table).scope(Scope::European).to_svg().contains("Third Party"));
+/// ```
+pub struct Chart<'a> {
+    poll_table: &'a PollTable,
+    metadata: Option<&'a PartyMetadata>,
+    parties: Option<Vec<String>>,
+    title: Option<String>,
+    width: u32,
+    height: u32,
+    window_days: i64,
+    scope: Scope,
+    thresholds: Vec<f32>,
+    elections: Vec<(NaiveDate, String)>,
+}
+
+impl<'a> Chart<'a> {
+    /// Creates a 1200 by 600 pixel chart of all parties, averaged over 28 days either side of each date.
+    pub fn new(poll_table: &'a PollTable) -> Self {
+        Chart {
+            poll_table,
+            metadata: None,
+            parties: None,
+            title: None,
+            width: 1200,
+            height: 600,
+            window_days: 28,
+            scope: Scope::National,
+            thresholds: Vec::new(),
+            elections: Vec::new(),
+        }
+    }
+
+    /// Colors parties by the colors in their metadata.
+    pub fn with_metadata(mut self, metadata: &'a PartyMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Only draws the given parties, in this order in the legend.
+    pub fn parties(mut self, parties: &[&str]) -> Self {
+        self.parties = Some(parties.iter().map(ToString::to_string).collect());
+        self
+    }
+
+    /// Sets the title. By default, the title is the jurisdiction's name.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Sets the size of the chart in pixels.
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets how many days before and after each date polls are included in the average line.
+    pub fn window_days(mut self, window_days: i64) -> Self {
+        self.window_days = window_days;
+        self
+    }
+
+    /// Sets the scope of the polls drawn and averaged, national by default.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Draws a dashed horizontal line at a threshold, in percent.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.thresholds.push(threshold);
+        self
+    }
+
+    /// Draws a labelled vertical line on an election day.
+    pub fn election(mut self, date: NaiveDate, label: &str) -> Self {
+        self.elections.push((date, label.to_string()));
+        self
+    }
+
+    /// Draws a vertical line on the day of every election of a scope in the history.
+    pub fn elections(mut self, history: &ElectionHistory, scope: &Scope) -> Self {
+        for election in history.elections() {
+            if election.scope() == scope {
+                self.elections
+                    .push((*election.date(), election.date().year().to_string()));
+            }
+        }
+        self
+    }
+
+    /// Renders the chart as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let (left, right, top, bottom) = MARGINS;
+        let plot_width = width - left - right;
+        let plot_height = height - top - bottom;
+
+        // Dots, per party, as (fieldwork midpoint, result), for polls of the averaged scope.
+        let mut dots: BTreeMap<&String, Vec<(NaiveDate, f32)>> = BTreeMap::new();
+        for poll in self
+            .poll_table
+            .polls
+            .iter()
+            .filter(|poll| poll.scope == self.scope)
+        {
+            for (party, result) in &poll.party_results {
+                if let PollOption::Some(PercentageOrSeats::Percentage(share)) = result {
+                    dots.entry(party)
+                        .or_default()
+                        .push((poll.fieldwork_midpoint(), share.value()));
+                }
+            }
+        }
+        let dates = dots.values().flatten().map(|(date, _)| *date);
+        let (Some(first), Some(last)) = (dates.clone().min(), dates.max()) else {
+            return format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}"/>"#,
+                self.width, self.height
+            );
+        };
+        let span = ((last - first).num_days()).max(1) as f32;
+        let x = |date: NaiveDate| left + (date - first).num_days() as f32 / span * plot_width;
+        let max_value = dots.values().flatten().map(|(_, v)| *v).fold(0.0, f32::max);
+        let y_max = ((max_value / 10.0).ceil() * 10.0).max(10.0);
+        let y = |value: f32| top + (1.0 - value / y_max) * plot_height;
+
+        // Average lines, per party, with gaps where no poll is in the window.
+        let options = AverageOptions::new()
+            .window_days(self.window_days)
+            .centered(true)
+            .scope(self.scope);
+        let steps = (last - first).num_days().clamp(1, AVERAGE_POINTS);
+        let mut averages: BTreeMap<String, Vec<Option<AveragePoint>>> = BTreeMap::new();
+        for step in 0..=steps {
+            let date = first + chrono::Duration::days((last - first).num_days() * step / steps);
+            let average = self.poll_table.average_at(&date, &options);
+            for party in dots.keys() {
+                let point = average
+                    .party(party)
+                    .map(|average| (date, average.value(), average.std_dev()));
+                averages.entry(party.to_string()).or_default().push(point);
+            }
+        }
+
+        let parties: Vec<String> = match &self.parties {
+            Some(parties) => parties
+                .iter()
+                .filter(|party| dots.contains_key(party))
+                .cloned()
+                .collect(),
+            None => {
+                // Largest parties first, by their latest average.
+                let mut parties: Vec<(String, f32)> = averages
+                    .iter()
+                    .map(|(party, points)| {
+                        let latest = points.iter().rev().flatten().next();
+                        (party.clone(), latest.map_or(0.0, |(_, value, _)| *value))
+                    })
+                    .collect();
+                parties.sort_by(|a, b| b.1.total_cmp(&a.1));
+                parties.into_iter().map(|(party, _)| party).collect()
+            }
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="DejaVu Sans, Arial, Helvetica, sans-serif" font-size="12">"#,
+            w = self.width,
+            h = self.height
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        let title = self
+            .title
+            .clone()
+            .unwrap_or_else(|| self.poll_table.jurisdiction.name().to_string());
+        let _ = writeln!(
+            svg,
+            r#"<text x="{left}" y="{}" font-size="18" font-weight="bold">{}</text>"#,
+            top / 2.0 + 6.0,
+            escape(&title)
+        );
+
+        // Horizontal grid lines and labels.
+        let y_step = if y_max > 40.0 { 10.0 } else { 5.0 };
+        let mut value = 0.0;
+        while value <= y_max {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{left}" x2="{}" y1="{y:.1}" y2="{y:.1}" stroke="#ddd"/><text x="{}" y="{:.1}" text-anchor="end">{value}%</text>"##,
+                left + plot_width,
+                left - 6.0,
+                y(value) + 4.0,
+                y = y(value),
+            );
+            value += y_step;
+        }
+
+        // Month ticks, spaced so that there are at most 8.
+        let months =
+            (last.year() - first.year()) * 12 + last.month0() as i32 - first.month0() as i32 + 1;
+        let step = [1, 2, 3, 6, 12, 24, 60]
+            .into_iter()
+            .find(|step| months / step <= 8)
+            .unwrap_or(120) as u32;
+        let first_month = first.month0() / step.min(12) * step.min(12) + 1;
+        let mut tick = NaiveDate::from_ymd_opt(first.year(), first_month, 1)
+            .expect("every month has a first day");
+        while tick <= last {
+            if tick >= first {
+                let label = if step >= 12 {
+                    tick.format("%Y")
+                } else {
+                    tick.format("%b %Y")
+                };
+                let _ = writeln!(
+                    svg,
+                    r##"<line x1="{x:.1}" x2="{x:.1}" y1="{top}" y2="{}" stroke="#eee"/><text x="{x:.1}" y="{}" text-anchor="middle">{label}</text>"##,
+                    top + plot_height,
+                    top + plot_height + 18.0,
+                    x = x(tick),
+                );
+            }
+            tick = tick + Months::new(step);
+        }
+
+        for threshold in &self.thresholds {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{left}" x2="{}" y1="{y:.1}" y2="{y:.1}" stroke="#555" stroke-dasharray="6 4"/>"##,
+                left + plot_width,
+                y = y(*threshold),
+            );
+        }
+        for (date, label) in &self.elections {
+            if *date < first || *date > last {
+                continue;
+            }
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{x:.1}" x2="{x:.1}" y1="{top}" y2="{}" stroke="#333" stroke-dasharray="2 3"/><text x="{:.1}" y="{}" font-size="10">{}</text>"##,
+                top + plot_height,
+                x(*date) + 3.0,
+                top + 12.0,
+                escape(label),
+                x = x(*date),
+            );
+        }
+
+        for (i, party) in parties.iter().enumerate() {
+            let color = self.color(party, i);
+            for (date, value) in &dots[party] {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{:.1}" cy="{:.1}" r="2.5" fill="{color}" fill-opacity="0.35"/>"#,
+                    x(*date),
+                    y(*value)
+                );
+            }
+            for segment in averages[party].split(Option::is_none) {
+                let points: Vec<&AveragePoint> = segment.iter().flatten().collect();
+                if points.len() < 2 {
+                    continue;
+                }
+                let upper = points
+                    .iter()
+                    .map(|(date, value, sd)| format!("{:.1},{:.1}", x(*date), y(value + sd)));
+                let lower = points.iter().rev().map(|(date, value, sd)| {
+                    format!("{:.1},{:.1}", x(*date), y((value - sd).max(0.0)))
+                });
+                let band: Vec<String> = upper.chain(lower).collect();
+                let line: Vec<String> = points
+                    .iter()
+                    .map(|(date, value, _)| format!("{:.1},{:.1}", x(*date), y(*value)))
+                    .collect();
+                let _ = writeln!(
+                    svg,
+                    r#"<polygon points="{}" fill="{color}" fill-opacity="0.15"/><polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+                    band.join(" "),
+                    line.join(" ")
+                );
+            }
+
+            let legend_y = top + 10.0 + i as f32 * 20.0;
+            let latest = averages[party].iter().rev().flatten().next();
+            let label = match latest {
+                Some((_, value, _)) => format!("{} {:.1}%", party, value),
+                None => party.clone(),
+            };
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{:.1}" width="12" height="12" fill="{color}"/><text x="{}" y="{:.1}">{}</text>"#,
+                left + plot_width + 15.0,
+                legend_y - 10.0,
+                left + plot_width + 32.0,
+                legend_y,
+                escape(&label)
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Renders the chart as a PNG image, using the system's fonts for text.
+    pub fn to_png(&self) -> Result<Vec<u8>, ChartError> {
+        let mut options = resvg::usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        let tree = resvg::usvg::Tree::from_str(&self.to_svg(), &options)?;
+        let mut pixmap =
+            resvg::tiny_skia::Pixmap::new(self.width, self.height).ok_or(ChartError::PngError)?;
+        resvg::render(
+            &tree,
+            resvg::tiny_skia::Transform::default(),
+            &mut pixmap.as_mut(),
+        );
+        pixmap.encode_png().map_err(|_| ChartError::PngError)
+    }
+
+    /// Writes the chart to a file, as SVG or PNG depending on its extension.
+    pub fn save(&self, path: &str) -> Result<(), ChartError> {
+        let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+        match extension {
+            Some("svg") => std::fs::write(path, self.to_svg())?,
+            Some("png") => std::fs::write(path, self.to_png()?)?,
+            _ => return Err(ChartError::UnsupportedFormatError),
+        }
+        Ok(())
+    }
+
+    fn color(&self, party: &str, index: usize) -> String {
+        self.metadata
+            .and_then(|metadata| metadata.get(party))
+            .and_then(|info| info.color())
+            .map(escape)
+            .unwrap_or_else(|| PALETTE[index % PALETTE.len()].to_string())
+    }
+}
+
+/// Escapes text for use in SVG content and attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,231 @@
+//! Coalition arithmetic on seat projections.
+use crate::errors::CoalitionError;
+use crate::party::PartyMetadata;
+use std::collections::HashMap;
+
+/// The most seat-holding parties [CoalitionCalculator::minimal_winning] enumerates coalitions of, as their number doubles with every party.
+pub const MAX_COALITION_PARTIES: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+/// The number of seats a coalition needs to govern.
+pub enum Majority {
+    /// More than half of all seats.
+    Simple,
+    /// At least two thirds of all seats.
+    Constitutional,
+    /// A fixed number of seats.
+    Custom(u32),
+}
+
+impl Majority {
+    /// Returns the number of seats needed out of a parliament of the given size.
+    pub fn seats_needed(&self, total_seats: u32) -> u32 {
+        match self {
+            Majority::Simple => total_seats / 2 + 1,
+            Majority::Constitutional => (2 * total_seats).div_ceil(3),
+            Majority::Custom(seats) => *seats,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A set of parties and the seats they hold together.
+pub struct Coalition {
+    members: Vec<String>,
+    seats: u32,
+    seats_needed: u32,
+    connected: Option<bool>,
+    ideological_range: Option<f32>,
+}
+
+impl Coalition {
+    /// Returns the members of the coalition, ordered from the most to the fewest seats.
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    /// Returns the total seats held by the coalition.
+    pub fn seats(&self) -> u32 {
+        self.seats
+    }
+
+    /// Returns whether the coalition reaches the majority threshold.
+    pub fn is_majority(&self) -> bool {
+        self.seats >= self.seats_needed
+    }
+
+    /// Returns the number of seats above the majority threshold, which is negative for coalitions without a majority.
+    pub fn surplus(&self) -> i64 {
+        self.seats as i64 - self.seats_needed as i64
+    }
+
+    /// Returns whether the members are adjacent on the left-right scale, with no other seat-holding party between them.
+    /// Returns None if the position of a member is unknown.
+    pub fn is_connected(&self) -> Option<bool> {
+        self.connected
+    }
+
+    /// Returns the distance between the leftmost and rightmost members on the left-right scale, if all positions are known.
+    pub fn ideological_range(&self) -> Option<f32> {
+        self.ideological_range
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Evaluates and enumerates coalitions for one seat projection.
+/// ```
+/// use europe_elects_csv::coalitions::*;
+/// use europe_elects_csv::party::*;
+/// use std::collections::HashMap;
+///
+/// let seats = HashMap::from([
+///     (String::from("Left"), 40),
+///     (String::from("Centre"), 15),
+///     (String::from("Right"), 45),
+/// ]);
+/// let mut metadata = PartyMetadata::new();
+/// metadata.insert("Left", PartyInfo::new().with_position(2.0));
+/// metadata.insert("Centre", PartyInfo::new().with_position(5.0));
+/// metadata.insert("Right", PartyInfo::new().with_position(8.0));
+///
+/// let calculator = CoalitionCalculator::new(&seats, Majority::Simple).with_metadata(&metadata);
+/// let mut coalitions = calculator.minimal_winning().unwrap();
+/// sort_by_connectedness(&mut coalitions);
+///
+/// assert_eq!(coalitions.len(), 3);
+/// assert_eq!(coalitions[0].members(), ["Left", "Centre"]);
+/// assert_eq!(coalitions[2].is_connected(), Some(false));
+/// assert!(!calculator.evaluate(&["Centre"]).is_majority());
+/// ```
+pub struct CoalitionCalculator<'a> {
+    seats: &'a HashMap<String, u32>,
+    seats_needed: u32,
+    metadata: Option<&'a PartyMetadata>,
+}
+
+impl<'a> CoalitionCalculator<'a> {
+    /// Creates a calculator for the given seat projection, such as one from [crate::seats::ElectoralSystem::project].
+    pub fn new(seats: &'a HashMap<String, u32>, majority: Majority) -> Self {
+        CoalitionCalculator {
+            seats,
+            seats_needed: majority.seats_needed(seats.values().sum()),
+            metadata: None,
+        }
+    }
+
+    /// Uses the positions in the party metadata to compute ideological connectedness.
+    pub fn with_metadata(mut self, metadata: &'a PartyMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Returns the number of seats a coalition needs.
+    pub fn seats_needed(&self) -> u32 {
+        self.seats_needed
+    }
+
+    /// Evaluates a user-specified coalition. Parties without seats count as holding none.
+    pub fn evaluate(&self, members: &[&str]) -> Coalition {
+        let mut members: Vec<String> = members.iter().map(|member| member.to_string()).collect();
+        members.sort_by(|a, b| {
+            self.seats_of(b)
+                .cmp(&self.seats_of(a))
+                .then_with(|| a.cmp(b))
+        });
+        members.dedup();
+
+        let seats = members.iter().map(|member| self.seats_of(member)).sum();
+        let positions: Option<Vec<f32>> = members.iter().map(|m| self.position(m)).collect();
+        let (connected, ideological_range) = match positions {
+            Some(positions) if !positions.is_empty() => {
+                let min = positions.iter().copied().fold(f32::INFINITY, f32::min);
+                let max = positions.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                let gap = self.seats.iter().any(|(party, seats)| {
+                    *seats > 0
+                        && !members.contains(party)
+                        && self
+                            .position(party)
+                            .is_some_and(|position| position > min && position < max)
+                });
+                (Some(!gap), Some(max - min))
+            }
+            _ => (None, None),
+        };
+
+        Coalition {
+            members,
+            seats,
+            seats_needed: self.seats_needed,
+            connected,
+            ideological_range,
+        }
+    }
+
+    /// Returns every coalition that reaches a majority but would lose it without any one of its members.
+    /// Fails if there are more than [MAX_COALITION_PARTIES] seat-holding parties.
+    pub fn minimal_winning(&self) -> Result<Vec<Coalition>, CoalitionError> {
+        let mut parties: Vec<&str> = self
+            .seats
+            .iter()
+            .filter(|(_, seats)| **seats > 0)
+            .map(|(party, _)| party.as_str())
+            .collect();
+        parties.sort();
+        if parties.len() > MAX_COALITION_PARTIES {
+            return Err(CoalitionError::TooManyPartiesError(parties.len()));
+        }
+
+        let mut coalitions = Vec::new();
+        for mask in 1u64..(1 << parties.len()) {
+            let members: Vec<&str> = (0..parties.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| parties[i])
+                .collect();
+            let seats: u32 = members.iter().map(|member| self.seats_of(member)).sum();
+            let smallest = members.iter().map(|member| self.seats_of(member)).min();
+            if seats >= self.seats_needed
+                && smallest.is_some_and(|smallest| seats - smallest < self.seats_needed)
+            {
+                coalitions.push(self.evaluate(&members));
+            }
+        }
+        sort_by_seats(&mut coalitions);
+        Ok(coalitions)
+    }
+
+    fn seats_of(&self, party: &str) -> u32 {
+        self.seats.get(party).copied().unwrap_or(0)
+    }
+
+    fn position(&self, party: &str) -> Option<f32> {
+        self.metadata?.get(party)?.position()
+    }
+}
+
+/// Sorts coalitions from the smallest to the largest number of seats, then by number of members.
+pub fn sort_by_seats(coalitions: &mut [Coalition]) {
+    coalitions.sort_by(|a, b| {
+        a.seats
+            .cmp(&b.seats)
+            .then_with(|| a.members.len().cmp(&b.members.len()))
+            .then_with(|| a.members.cmp(&b.members))
+    });
+}
+
+/// Sorts connected coalitions first, then by ideological range from narrowest to widest, then by seats.
+/// Coalitions with unknown positions come last.
+pub fn sort_by_connectedness(coalitions: &mut [Coalition]) {
+    sort_by_seats(coalitions);
+    coalitions.sort_by(|a, b| {
+        let rank = |c: &Coalition| match c.connected {
+            Some(true) => 0,
+            Some(false) => 1,
+            None => 2,
+        };
+        rank(a).cmp(&rank(b)).then_with(|| {
+            a.ideological_range
+                .unwrap_or(f32::INFINITY)
+                .total_cmp(&b.ideological_range.unwrap_or(f32::INFINITY))
+        })
+    });
+}
@@ -0,0 +1,81 @@
+//! Sets of [PollTable]s covering several jurisdictions.
+use crate::errors::{PollTableCollectionError, PollTableTryFromPathError};
+use crate::{Jurisdiction, PollTable};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+/// A set of [PollTable]s, such as a directory of Europe Elects .csv files, with at most one table per jurisdiction.
+pub struct PollTableCollection {
+    tables: Vec<PollTable>,
+}
+
+impl PollTableCollection {
+    /// Creates a collection from [PollTable]s. If several tables share a jurisdiction, only the last one is kept.
+    pub fn new(tables: Vec<PollTable>) -> Self {
+        let mut collection = PollTableCollection { tables: Vec::new() };
+        for table in tables {
+            collection.insert(table);
+        }
+        collection
+    }
+
+    /// Loads every .csv file in a directory whose name is a Europe Elects jurisdiction code, such as "de.csv".
+    /// Other files are ignored, but a matching file that cannot be parsed is an error.
+    pub fn try_from_dir(path: &str) -> Result<PollTableCollection, PollTableCollectionError> {
+        let mut collection = PollTableCollection::default();
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("csv") {
+                continue;
+            }
+            let file = path
+                .to_str()
+                .ok_or(PollTableCollectionError::InvalidPathError)?;
+            match PollTable::try_from_path(file) {
+                Ok(table) => collection.insert(table),
+                Err(PollTableTryFromPathError::InvalidJurisdictionError) => continue,
+                Err(error) => {
+                    return Err(PollTableCollectionError::PollTableError {
+                        file: Path::new(file).display().to_string(),
+                        source: error,
+                    })
+                }
+            }
+        }
+        Ok(collection)
+    }
+
+    /// Adds a table, replacing any table of the same jurisdiction. Tables are kept in order of jurisdiction code.
+    pub fn insert(&mut self, table: PollTable) {
+        let code = table.jurisdiction.code();
+        match self
+            .tables
+            .binary_search_by(|other| other.jurisdiction.code().cmp(code))
+        {
+            Ok(i) => self.tables[i] = table,
+            Err(i) => self.tables.insert(i, table),
+        }
+    }
+
+    /// Returns all tables, in order of jurisdiction code.
+    pub fn tables(&self) -> &[PollTable] {
+        &self.tables
+    }
+
+    /// Returns the table of a jurisdiction, if the collection has one.
+    pub fn get(&self, jurisdiction: Jurisdiction) -> Option<&PollTable> {
+        self.tables
+            .iter()
+            .find(|table| table.jurisdiction == jurisdiction)
+    }
+
+    /// Returns the number of tables in the collection.
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Returns whether the collection has no tables.
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+}
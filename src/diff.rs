@@ -0,0 +1,318 @@
+//! Differences between two versions of the same poll file.
+use crate::identity::PollId;
+use crate::{Poll, PollOption, PollTable};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize)]
+/// Identifies a poll in one version of a file.
+pub struct PollRef {
+    /// The id of the poll.
+    pub id: PollId,
+    /// The index of the poll in its [PollTable].
+    pub index: usize,
+    /// The polling firm.
+    pub polling_firm: String,
+    /// The commissioners, as written in the file.
+    pub commissioners: String,
+    /// The first day of fieldwork.
+    pub fieldwork_start: NaiveDate,
+    /// The last day of fieldwork.
+    pub fieldwork_end: NaiveDate,
+    /// The scope, as written in the file.
+    pub scope: String,
+}
+
+impl PollRef {
+    pub(crate) fn new(index: usize, poll: &Poll) -> Self {
+        PollRef {
+            id: poll.id(),
+            index,
+            polling_firm: poll.polling_firm.clone(),
+            commissioners: poll.commissioners.to_string(),
+            fieldwork_start: poll.fieldwork_start,
+            fieldwork_end: poll.fieldwork_end,
+            scope: poll.scope.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for PollRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}), {} to {}, {}",
+            self.polling_firm,
+            self.commissioners,
+            self.fieldwork_start,
+            self.fieldwork_end,
+            self.scope
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A field whose value differs between the two versions of a poll. Values are written as in the .csv file.
+pub struct FieldChange {
+    /// The column name in the new version.
+    pub field: String,
+    /// The value in the old version.
+    pub old: String,
+    /// The value in the new version.
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A poll present in both versions whose fields differ.
+pub struct PollDiff {
+    /// The poll in the old version.
+    pub old: PollRef,
+    /// The index of the poll in the new version.
+    pub new_index: usize,
+    /// The fields that differ.
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A party column which has a different name in the new version but the same values in every matched poll.
+pub struct RenamedColumn {
+    /// The column name in the old version.
+    pub old: String,
+    /// The column name in the new version.
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// The changes between two versions of a [PollTable], as returned by [PollTable::diff].
+///
+/// Polls are matched by their [PollId], which is derived from their polling firm, commissioners, fieldwork dates and scope.
+pub struct PollTableDiff {
+    /// Polls only present in the new version.
+    pub added: Vec<PollRef>,
+    /// Polls only present in the old version.
+    pub removed: Vec<PollRef>,
+    /// Polls present in both versions with different fields.
+    pub changed: Vec<PollDiff>,
+    /// Party columns that were renamed.
+    pub renamed_columns: Vec<RenamedColumn>,
+    /// Party columns only present in the new version, apart from renamed ones.
+    pub added_columns: Vec<String>,
+    /// Party columns only present in the old version, apart from renamed ones.
+    pub removed_columns: Vec<String>,
+}
+
+impl PollTableDiff {
+    /// Returns whether the two versions contain the same polls with the same fields.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.renamed_columns.is_empty()
+            && self.added_columns.is_empty()
+            && self.removed_columns.is_empty()
+    }
+
+    /// Returns the changeset as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("PollTableDiff should serialize to JSON")
+    }
+}
+
+impl fmt::Display for PollTableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for column in &self.renamed_columns {
+            writeln!(f, "Renamed column: {} -> {}", column.old, column.new)?;
+        }
+        for column in &self.added_columns {
+            writeln!(f, "Added column: {column}")?;
+        }
+        for column in &self.removed_columns {
+            writeln!(f, "Removed column: {column}")?;
+        }
+        for poll in &self.added {
+            writeln!(f, "+ {poll}")?;
+        }
+        for poll in &self.removed {
+            writeln!(f, "- {poll}")?;
+        }
+        for poll in &self.changed {
+            writeln!(f, "~ {}", poll.old)?;
+            for change in &poll.changes {
+                writeln!(f, "    {}: {} -> {}", change.field, change.old, change.new)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PollTable {
+    /// Compares two versions of the same poll file.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// let old = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,21%,30%,49%
+    /// Other Polling,Not Available,2024-03-01,2024-03-02,National,1000,Provided,Not Available,1%,20%,30%,50%";
+    /// let new = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// New Polling,Not Available,2024-03-10,2024-03-12,National,1500,Provided,Not Available,1%,23%,31%,46%
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,22%,30%,48%";
+    /// let old = PollTable::from_str(old, "de").unwrap();
+    /// let new = PollTable::from_str(new, "de").unwrap();
+    /// let diff = PollTable::diff(&old, &new);
+    ///
+    /// assert_eq!(diff.added[0].polling_firm, "New Polling");
+    /// assert_eq!(diff.removed[0].polling_firm, "Other Polling");
+    /// assert_eq!(diff.changed[0].changes[0].field, "First Party");
+    /// assert_eq!(diff.changed[0].changes[0].old, "21%");
+    /// assert_eq!(diff.changed[0].changes[0].new, "22%");
+    ///
+    /// // Columns without a result in any matched poll are not taken for renames of each other.
+    /// let old = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Third Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,Not Available,70%";
+    /// let new = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Fourth Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,Not Available,70%";
+    /// let diff = PollTable::diff(&PollTable::from_str(old, "de").unwrap(), &PollTable::from_str(new, "de").unwrap());
+    ///
+    /// assert!(diff.renamed_columns.is_empty());
+    /// assert_eq!(diff.removed_columns, vec!["Third Party"]);
+    /// ```
+    pub fn diff(old: &PollTable, new: &PollTable) -> PollTableDiff {
+        let mut unmatched: HashMap<PollId, Vec<usize>> = HashMap::new();
+        for (i, poll) in old.polls.iter().enumerate().rev() {
+            unmatched.entry(poll.id()).or_default().push(i);
+        }
+
+        let mut diff = PollTableDiff::default();
+        let mut matched: Vec<(usize, usize)> = Vec::new();
+        for (j, poll) in new.polls.iter().enumerate() {
+            match unmatched.get_mut(&poll.id()).and_then(Vec::pop) {
+                Some(i) => matched.push((i, j)),
+                None => diff.added.push(PollRef::new(j, poll)),
+            }
+        }
+        let mut removed: Vec<usize> = unmatched.into_values().flatten().collect();
+        removed.sort();
+        diff.removed = removed
+            .into_iter()
+            .map(|i| PollRef::new(i, &old.polls[i]))
+            .collect();
+
+        let old_columns = columns(old);
+        let new_columns = columns(new);
+        let mut only_old: Vec<&String> = old_columns.difference(&new_columns).copied().collect();
+        let mut only_new: Vec<&String> = new_columns.difference(&old_columns).copied().collect();
+
+        // A column is considered renamed if it has exactly the same values as a new column in every matched poll,
+        // and a result in at least one of them, so columns that are empty throughout are not paired up.
+        let mut renames: HashMap<&String, &String> = HashMap::new();
+        only_old.retain(|old_column| {
+            let renamed = only_new.iter().position(|new_column| {
+                let pairs = || {
+                    matched.iter().map(|(i, j)| {
+                        (
+                            old.polls[*i].party_results.get(*old_column),
+                            new.polls[*j].party_results.get(*new_column),
+                        )
+                    })
+                };
+                pairs().any(|(before, _)| matches!(before, Some(PollOption::Some(_))))
+                    && pairs().all(|(before, after)| {
+                        before.map(ToString::to_string) == after.map(ToString::to_string)
+                    })
+            });
+            match renamed {
+                Some(position) => {
+                    renames.insert(only_new.remove(position), old_column);
+                    false
+                }
+                None => true,
+            }
+        });
+        diff.added_columns = only_new.into_iter().cloned().collect();
+        diff.removed_columns = only_old.into_iter().cloned().collect();
+        let mut renamed: Vec<RenamedColumn> = renames
+            .iter()
+            .map(|(new, old)| RenamedColumn {
+                old: old.to_string(),
+                new: new.to_string(),
+            })
+            .collect();
+        renamed.sort_by(|a, b| a.old.cmp(&b.old));
+        diff.renamed_columns = renamed;
+
+        for (i, j) in matched {
+            let changes = field_changes(&old.polls[i], &new.polls[j], &new_columns, &renames);
+            if !changes.is_empty() {
+                diff.changed.push(PollDiff {
+                    old: PollRef::new(i, &old.polls[i]),
+                    new_index: j,
+                    changes,
+                });
+            }
+        }
+        diff
+    }
+}
+
+fn columns(poll_table: &PollTable) -> BTreeSet<&String> {
+    poll_table
+        .polls
+        .iter()
+        .flat_map(|poll| poll.party_results.keys())
+        .collect()
+}
+
+fn field_changes(
+    old: &Poll,
+    new: &Poll,
+    new_columns: &BTreeSet<&String>,
+    renames: &HashMap<&String, &String>,
+) -> Vec<FieldChange> {
+    let mut fields = vec![
+        (
+            String::from("Sample Size"),
+            old.sample_size.to_string(),
+            new.sample_size.to_string(),
+        ),
+        (
+            String::from("Sample Size Qualification"),
+            old.sample_size_qualification.to_string(),
+            new.sample_size_qualification.to_string(),
+        ),
+        (
+            String::from("Participation"),
+            old.participation.to_string(),
+            new.participation.to_string(),
+        ),
+        (
+            String::from("Precision"),
+            old.precision.to_string(),
+            new.precision.to_string(),
+        ),
+    ];
+    for column in new_columns {
+        let old_column = renames.get(column).copied().unwrap_or(column);
+        // Columns only present in one version are reported once for the whole file.
+        if let (Some(before), Some(after)) = (
+            old.party_results.get(old_column),
+            new.party_results.get(*column),
+        ) {
+            fields.push((column.to_string(), before.to_string(), after.to_string()));
+        }
+    }
+    fields.push((
+        String::from("Other"),
+        old.other.to_string(),
+        new.other.to_string(),
+    ));
+
+    fields
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange { field, old, new })
+        .collect()
+}
@@ -0,0 +1,203 @@
+//! Detection of polls that appear more than once, possibly with slightly different details.
+use crate::collection::PollTableCollection;
+use crate::{Jurisdiction, Poll, PollOption, PollTable};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How closely two polls match.
+pub enum DuplicateKind {
+    /// Every field of the two polls is the same.
+    Exact,
+    /// The polls share a polling firm and overlapping fieldwork, with similar results.
+    Near,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Where a poll is found within a [PollTable] or [PollTableCollection].
+pub struct PollLocation {
+    /// The jurisdiction of the poll's table.
+    pub jurisdiction: Jurisdiction,
+    /// The index of the poll in its table.
+    pub index: usize,
+}
+
+#[derive(Debug, Clone)]
+/// A pair of polls which are likely to be the same poll.
+pub struct Duplicate {
+    /// The first poll, which comes earlier in its table or in a table with an earlier jurisdiction code.
+    pub first: PollLocation,
+    /// The second poll.
+    pub second: PollLocation,
+    /// Whether the polls are exact or near duplicates.
+    pub kind: DuplicateKind,
+    /// How similar the polls are, from 0 to 1, where exact duplicates have a similarity of 1.
+    pub similarity: f32,
+}
+
+#[derive(Debug, Clone)]
+/// Settings for near-duplicate detection.
+pub struct DuplicateOptions {
+    min_similarity: f32,
+    tolerance: f32,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        DuplicateOptions::new()
+    }
+}
+
+impl DuplicateOptions {
+    /// Creates options with a minimum similarity of 0.75 and a tolerance of 2 percentage points.
+    pub fn new() -> Self {
+        DuplicateOptions {
+            min_similarity: 0.75,
+            tolerance: 2.0,
+        }
+    }
+
+    /// Sets the similarity from which two polls are reported as near duplicates.
+    pub fn min_similarity(mut self, min_similarity: f32) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Sets the mean absolute difference between party results at which results are considered entirely dissimilar.
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+/// Compares two polls, returning their similarity if they are duplicates.
+/// Near duplicates must be by the same firm and of the same scope.
+///
+/// Similarity is the mean of the overlap of their fieldwork periods, as a share of the combined period,
+/// and of the similarity of the results of the parties available in both polls.
+fn compare(a: &Poll, b: &Poll, options: &DuplicateOptions) -> Option<(DuplicateKind, f32)> {
+    if a.content_hash() == b.content_hash() {
+        return Some((DuplicateKind::Exact, 1.0));
+    }
+    if a.polling_firm.to_lowercase() != b.polling_firm.to_lowercase() || a.scope != b.scope {
+        return None;
+    }
+
+    let (start, end) = (
+        a.fieldwork_start.max(b.fieldwork_start),
+        a.fieldwork_end.min(b.fieldwork_end),
+    );
+    if start > end {
+        return None;
+    }
+    let first_day = a.fieldwork_start.min(b.fieldwork_start);
+    let last_day = a.fieldwork_end.max(b.fieldwork_end);
+    let overlap = (end - start).num_days() + 1;
+    let combined = (last_day - first_day).num_days() + 1;
+    let date_similarity = overlap as f32 / combined as f32;
+
+    let differences: Vec<f32> = a
+        .party_results
+        .iter()
+        .filter_map(
+            |(party, result)| match (result, b.party_results.get(party)?) {
+                (PollOption::Some(x), PollOption::Some(y)) => Some((x.value() - y.value()).abs()),
+                _ => None,
+            },
+        )
+        .collect();
+    if differences.is_empty() {
+        return None;
+    }
+    let mean_difference = differences.iter().sum::<f32>() / differences.len() as f32;
+    let result_similarity = (1.0 - mean_difference / options.tolerance).max(0.0);
+
+    let similarity = (date_similarity + result_similarity) / 2.0;
+    (similarity >= options.min_similarity).then_some((DuplicateKind::Near, similarity))
+}
+
+/// Finds duplicates among polls from several tables, comparing only polls by the same firm.
+fn find(tables: &[&PollTable], options: &DuplicateOptions) -> Vec<Duplicate> {
+    let mut by_firm: BTreeMap<String, Vec<(PollLocation, &Poll)>> = BTreeMap::new();
+    for table in tables {
+        for (index, poll) in table.polls.iter().enumerate() {
+            let location = PollLocation {
+                jurisdiction: table.jurisdiction,
+                index,
+            };
+            by_firm
+                .entry(poll.polling_firm.to_lowercase())
+                .or_default()
+                .push((location, poll));
+        }
+    }
+
+    let mut duplicates = Vec::new();
+    for polls in by_firm.values() {
+        for (i, (first, a)) in polls.iter().enumerate() {
+            for (second, b) in &polls[i + 1..] {
+                if let Some((kind, similarity)) = compare(a, b, options) {
+                    duplicates.push(Duplicate {
+                        first: *first,
+                        second: *second,
+                        kind,
+                        similarity,
+                    });
+                }
+            }
+        }
+    }
+    duplicates.sort_by(|a, b| {
+        let key = |d: &Duplicate| (d.first.jurisdiction.code(), d.first.index, d.second.index);
+        key(a).cmp(&key(b))
+    });
+    duplicates
+}
+
+impl PollTable {
+    /// Finds exact and near duplicates within the table.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::duplicates::*;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,60%,30%,10%
+    /// Epic Polling,The Daily Slug,2024-03-05,2024-03-08,National,2054,Provided,Not Available,1%,60%,31%,9%
+    /// Epic Polling,The Daily Snail,2024-02-06,2024-02-08,National,2054,Provided,Not Available,1%,58%,32%,10%
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,European,2054,Provided,Not Available,1%,59%,30%,11%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let duplicates = poll_table.find_duplicates(&DuplicateOptions::new());
+    ///
+    /// assert_eq!(duplicates.len(), 1);
+    /// assert_eq!(duplicates[0].kind, DuplicateKind::Near);
+    /// assert_eq!(poll_table.deduplicated(&DuplicateOptions::new()).polls().len(), 3);
+    /// ```
+    pub fn find_duplicates(&self, options: &DuplicateOptions) -> Vec<Duplicate> {
+        find(&[self], options)
+    }
+
+    /// Returns a copy of the table without the second poll of every duplicate pair.
+    pub fn deduplicated(&self, options: &DuplicateOptions) -> PollTable {
+        let removed: HashSet<usize> = self
+            .find_duplicates(options)
+            .into_iter()
+            .map(|duplicate| duplicate.second.index)
+            .collect();
+        PollTable::new(
+            self.polls
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !removed.contains(i))
+                .map(|(_, poll)| poll.clone())
+                .collect(),
+            self.jurisdiction,
+        )
+    }
+}
+
+impl PollTableCollection {
+    /// Finds exact and near duplicates within and across all tables in the collection,
+    /// such as a poll which appears in both a national and a regional file.
+    pub fn find_duplicates(&self, options: &DuplicateOptions) -> Vec<Duplicate> {
+        let tables: Vec<&PollTable> = self.tables().iter().collect();
+        find(&tables, options)
+    }
+}
@@ -0,0 +1,276 @@
+//! Official election results, which serve as a baseline for poll analysis.
+use crate::errors::ElectionHistoryError;
+use crate::{init_jurisdiction, Jurisdiction, Percentage, PercentageOrSeats, PollOption, Scope};
+use chrono::NaiveDate;
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default)]
+/// A party's result in an election.
+pub struct PartyResult {
+    vote_share: Option<f32>,
+    seats: Option<f32>,
+}
+
+impl PartyResult {
+    /// Returns the share of the vote won by the party, in percent.
+    pub fn vote_share(&self) -> Option<f32> {
+        self.vote_share
+    }
+
+    /// Returns the number of seats won by the party.
+    pub fn seats(&self) -> Option<f32> {
+        self.seats
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The result of one election, keyed by the same party column names as the poll files.
+pub struct ElectionResult {
+    jurisdiction: Jurisdiction,
+    scope: Scope,
+    date: NaiveDate,
+    turnout: PollOption<Percentage>,
+    parties: HashMap<String, PartyResult>,
+    other: PartyResult,
+}
+
+impl ElectionResult {
+    /// Creates an election result without any party results.
+    pub fn new(
+        jurisdiction: Jurisdiction,
+        scope: Scope,
+        date: NaiveDate,
+        turnout: PollOption<Percentage>,
+    ) -> Self {
+        ElectionResult {
+            jurisdiction,
+            scope,
+            date,
+            turnout,
+            parties: HashMap::new(),
+            other: PartyResult::default(),
+        }
+    }
+
+    /// Sets a party's vote share, in percent, and number of seats.
+    pub fn with_party(mut self, party: &str, vote_share: Option<f32>, seats: Option<f32>) -> Self {
+        self.parties
+            .insert(party.to_string(), PartyResult { vote_share, seats });
+        self
+    }
+
+    /// Returns the jurisdiction of the election.
+    pub fn jurisdiction(&self) -> &Jurisdiction {
+        &self.jurisdiction
+    }
+
+    /// Returns whether the election was national or to the European Parliament.
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    /// Returns the election day.
+    pub fn date(&self) -> &NaiveDate {
+        &self.date
+    }
+
+    /// Returns the turnout.
+    pub fn turnout(&self) -> &PollOption<Percentage> {
+        &self.turnout
+    }
+
+    /// Returns the result of every party.
+    pub fn parties(&self) -> &HashMap<String, PartyResult> {
+        &self.parties
+    }
+
+    /// Returns the result of a party.
+    pub fn party(&self, party: &str) -> Option<&PartyResult> {
+        self.parties.get(party)
+    }
+
+    /// Returns the combined result of all other parties.
+    pub fn other(&self) -> &PartyResult {
+        &self.other
+    }
+
+    /// Returns the vote share of every party for which it is known, in percent.
+    pub fn vote_shares(&self) -> HashMap<String, f32> {
+        self.parties
+            .iter()
+            .filter_map(|(party, result)| Some((party.clone(), result.vote_share?)))
+            .collect()
+    }
+
+    /// Returns the total number of seats won by all parties, including other parties.
+    pub fn total_seats(&self) -> f32 {
+        self.parties
+            .values()
+            .chain(std::iter::once(&self.other))
+            .filter_map(|result| result.seats)
+            .sum()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ElectionRow {
+    #[serde(rename = "Date")]
+    date: NaiveDate,
+    #[serde(rename = "Scope")]
+    scope: Scope,
+    #[serde(rename = "Turnout")]
+    turnout: PollOption<Percentage>,
+    #[serde(flatten)]
+    party_results: HashMap<String, PollOption<PercentageOrSeats>>,
+    #[serde(rename = "Other")]
+    other: PollOption<PercentageOrSeats>,
+}
+
+fn merge(result: &mut PartyResult, value: &PollOption<PercentageOrSeats>) {
+    match value {
+        PollOption::Some(PercentageOrSeats::Percentage(share)) => {
+            result.vote_share = Some(share.value())
+        }
+        PollOption::Some(PercentageOrSeats::Seats(seats)) => result.seats = Some(seats.value()),
+        PollOption::NotAvailable => {}
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The election results of one jurisdiction, in chronological order.
+///
+/// Results are read from a .csv file laid out like the poll files, with the columns
+/// `Date,Scope,Turnout`, one column per party and `Other`. Cells ending in `%` are vote shares and other numbers are seats,
+/// so an election may be given as one row of vote shares and one row of seats with the same date and scope.
+pub struct ElectionHistory {
+    jurisdiction: Jurisdiction,
+    elections: Vec<ElectionResult>,
+}
+
+impl ElectionHistory {
+    /// Creates a history from election results, which are sorted by date.
+    pub fn new(jurisdiction: Jurisdiction, mut elections: Vec<ElectionResult>) -> Self {
+        elections.sort_by_key(|election| election.date);
+        ElectionHistory {
+            jurisdiction,
+            elections,
+        }
+    }
+
+    /// Attempts to load election results from a .csv file whose name is a Europe Elects jurisdiction code, such as "de.csv".
+    pub fn try_from_path(path: &str) -> Result<ElectionHistory, ElectionHistoryError> {
+        let path = Path::new(path);
+        let extension = path
+            .extension()
+            .and_then(|os_str| os_str.to_str())
+            .ok_or(ElectionHistoryError::InvalidPathError)?;
+        if extension != "csv" {
+            return Err(ElectionHistoryError::NotCsvError);
+        }
+        let filename = path
+            .file_stem()
+            .and_then(|os_str| os_str.to_str())
+            .ok_or(ElectionHistoryError::InvalidPathError)?;
+        let jurisdiction = *init_jurisdiction()
+            .get(filename)
+            .ok_or(ElectionHistoryError::InvalidJurisdictionError)?;
+
+        let rdr = ReaderBuilder::new().trim(csv::Trim::All).from_path(path)?;
+        ElectionHistory::from_reader(rdr, jurisdiction)
+    }
+
+    /// Creates an [ElectionHistory] from .csv data, for the jurisdiction with the given code.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::elections::ElectionHistory;
+    /// let example = "Date,Scope,Turnout,First Party,Second Party,Other
+    /// 2021-09-26,National,76.4%,25.7%,24.1%,50.2%
+    /// 2021-09-26,National,76.4%,206,197,333
+    /// 2017-09-24,National,76.2%,20.5%,32.9%,46.6%";
+    /// let history = ElectionHistory::from_str(example, "de").unwrap();
+    /// let latest = history.latest(&Scope::National).unwrap();
+    ///
+    /// assert_eq!(history.elections().len(), 2);
+    /// assert_eq!(latest.party("First Party").unwrap().vote_share(), Some(25.7));
+    /// assert_eq!(latest.party("First Party").unwrap().seats(), Some(206.0));
+    /// assert_eq!(latest.total_seats(), 736.0);
+    /// ```
+    pub fn from_str(s: &str, jurisdiction: &str) -> Result<ElectionHistory, ElectionHistoryError> {
+        let jurisdiction = *init_jurisdiction()
+            .get(jurisdiction)
+            .ok_or(ElectionHistoryError::InvalidJurisdictionError)?;
+        let rdr = ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(s.as_bytes());
+        ElectionHistory::from_reader(rdr, jurisdiction)
+    }
+
+    fn from_reader<R: std::io::Read>(
+        mut rdr: csv::Reader<R>,
+        jurisdiction: Jurisdiction,
+    ) -> Result<ElectionHistory, ElectionHistoryError> {
+        let mut elections: Vec<ElectionResult> = Vec::new();
+        for row in rdr.deserialize() {
+            let row: ElectionRow = row?;
+            let position = elections
+                .iter()
+                .position(|election| election.date == row.date && election.scope == row.scope);
+            let election = match position {
+                Some(i) => &mut elections[i],
+                None => {
+                    elections.push(ElectionResult::new(
+                        jurisdiction,
+                        row.scope,
+                        row.date,
+                        PollOption::NotAvailable,
+                    ));
+                    elections.last_mut().expect("an election was just added")
+                }
+            };
+            if row.turnout.is_some() {
+                election.turnout = row.turnout;
+            }
+            for (party, value) in &row.party_results {
+                merge(election.parties.entry(party.clone()).or_default(), value);
+            }
+            merge(&mut election.other, &row.other);
+        }
+        Ok(ElectionHistory::new(jurisdiction, elections))
+    }
+
+    /// Returns the jurisdiction of the elections.
+    pub fn jurisdiction(&self) -> &Jurisdiction {
+        &self.jurisdiction
+    }
+
+    /// Returns all elections, from oldest to newest.
+    pub fn elections(&self) -> &[ElectionResult] {
+        &self.elections
+    }
+
+    /// Returns the election of a scope held on a date.
+    pub fn get(&self, scope: &Scope, date: &NaiveDate) -> Option<&ElectionResult> {
+        self.elections
+            .iter()
+            .find(|election| election.scope == *scope && election.date == *date)
+    }
+
+    /// Returns the most recent election of a scope.
+    pub fn latest(&self, scope: &Scope) -> Option<&ElectionResult> {
+        self.elections
+            .iter()
+            .rev()
+            .find(|election| election.scope == *scope)
+    }
+
+    /// Returns the most recent election of a scope held strictly before a date.
+    pub fn latest_before(&self, scope: &Scope, date: &NaiveDate) -> Option<&ElectionResult> {
+        self.elections
+            .iter()
+            .rev()
+            .find(|election| election.scope == *scope && election.date < *date)
+    }
+}
@@ -24,4 +24,143 @@ pub enum PollTableFromStrError {
 pub enum RawPollTableFromStrError {
     #[error("Failed to create ReaderBuilder from specified &str")]
     ReaderBuilderError(#[from] csv::Error),
-}
\ No newline at end of file
+}
+
+#[derive(Error, Debug)]
+pub enum SimulationError {
+    #[error("Vote share estimate contains no parties")]
+    NoPartiesError,
+    #[error("Distribution parameters are not valid")]
+    InvalidParameterError,
+    #[error("Correlation matrix does not match the number of parties")]
+    CorrelationDimensionError,
+    #[error("Correlation matrix is not positive definite")]
+    NotPositiveDefiniteError,
+}
+
+#[derive(Error, Debug)]
+pub enum CoalitionError {
+    #[error("Too many seat-holding parties to enumerate coalitions: {0}")]
+    TooManyPartiesError(usize),
+}
+
+#[derive(Error, Debug)]
+pub enum PartyMetadataError {
+    #[error("Failed to read party metadata file")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to create ReaderBuilder from party metadata")]
+    ReaderBuilderError(#[from] csv::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum BlocMapError {
+    #[error("Failed to read bloc map file")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to create ReaderBuilder from bloc map")]
+    ReaderBuilderError(#[from] csv::Error),
+    #[error("Bloc {0} has the same name as a party column outside any bloc")]
+    NameCollisionError(String),
+}
+
+#[derive(Error, Debug)]
+pub enum SocialPostError {
+    #[error("No poll exists at the specified index")]
+    IndexOutOfRangeError,
+    #[error("Post does not fit within the platform's character limit")]
+    TooLongError,
+}
+
+#[derive(Error, Debug)]
+pub enum PollTableCollectionError {
+    #[error("Failed to read directory")]
+    IoError(#[from] std::io::Error),
+    #[error("Path in directory is not a valid OsStr")]
+    InvalidPathError,
+    #[error("Failed to read {file}")]
+    PollTableError {
+        file: String,
+        #[source]
+        source: PollTableTryFromPathError,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum ElectionHistoryError {
+    #[error("Failed to create ReaderBuilder from election results")]
+    ReaderBuilderError(#[from] csv::Error),
+    #[error("Specified file is not a .csv")]
+    NotCsvError,
+    #[error("Specified path is not a valid OsStr")]
+    InvalidPathError,
+    #[error("Filename does not match a valid Europe Elects jurisdiction")]
+    InvalidJurisdictionError,
+}
+
+#[cfg(feature = "chart")]
+#[derive(Error, Debug)]
+pub enum ChartError {
+    #[error("Failed to write chart")]
+    IoError(#[from] std::io::Error),
+    #[error("Chart file must have a .svg or .png extension")]
+    UnsupportedFormatError,
+    #[error("Failed to parse generated SVG")]
+    SvgError(#[from] resvg::usvg::Error),
+    #[error("Failed to render chart as PNG")]
+    PngError,
+}
+
+#[cfg(feature = "server")]
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("Failed to load poll tables")]
+    CollectionError(#[from] PollTableCollectionError),
+    #[error("Failed to listen on the specified address")]
+    BindError(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[cfg(feature = "xlsx")]
+#[derive(Error, Debug)]
+pub enum SpreadsheetError {
+    #[error("Failed to write spreadsheet")]
+    WorkbookError(#[from] rust_xlsxwriter::XlsxError),
+}
+
+#[derive(Error, Debug)]
+pub enum WatchError {
+    #[error("Failed to read the watched directory or write the feed")]
+    IoError(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum AlertRulesError {
+    #[error("Failed to read alert rules file")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to read alert rules .csv data")]
+    ReaderBuilderError(#[from] csv::Error),
+    #[error("Unknown alert rule: {0}")]
+    UnknownRuleError(String),
+    #[error("Alert rule {0} needs a party")]
+    MissingPartyError(String),
+    #[error("Alert rule {0} needs a value")]
+    MissingValueError(String),
+    #[error("Invalid jurisdiction code in alert rule: {0}")]
+    InvalidJurisdictionError(String),
+}
+
+#[derive(Error, Debug)]
+pub enum ColumnMappingError {
+    #[error("Failed to read column mapping file")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to create ReaderBuilder from column mapping")]
+    ReaderBuilderError(#[from] csv::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum WikitableError {
+    #[error("No wikitable found in the wikitext")]
+    NoTableError,
+    #[error("Wikitable has no {0} column")]
+    MissingColumnError(&'static str),
+    #[error("Invalid fieldwork date in wikitable: {0}")]
+    InvalidDateError(String),
+}
@@ -0,0 +1,485 @@
+//! A C ABI over [PollTable], declared in `include/europe_elects_csv.h`.
+//!
+//! Tables are opaque handles created by `ee_poll_table_from_path` or `ee_poll_table_from_buffer` and released with `ee_poll_table_free`.
+//! Strings returned by the library are NUL-terminated and owned by their table, so they stay valid until it is freed.
+//! Every fallible function returns an [EeStatus], and a panic inside the library is caught and reported as `EE_PANIC` rather than unwinding into the caller.
+use crate::errors::{PollTableFromStrError, PollTableTryFromPathError};
+use crate::{PercentageOrSeats, PollOption, PollTable, Scope};
+use chrono::{Datelike, NaiveDate};
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::AssertUnwindSafe;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The result of a call, mapped from the library's error types.
+pub enum EeStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The file could not be read or its .csv data could not be parsed.
+    CsvError = 3,
+    /// The path does not end in ".csv".
+    NotCsv = 4,
+    /// The path has no valid file name.
+    InvalidPath = 5,
+    /// The file name or code is not a Europe Elects jurisdiction.
+    InvalidJurisdiction = 6,
+    /// A poll or party index is out of range.
+    IndexOutOfRange = 7,
+    /// The requested field is "Not Available" in the poll.
+    NotAvailable = 8,
+    /// The library failed internally, such as on a value it could not parse. The call had no effect.
+    Panic = 9,
+}
+
+impl From<PollTableTryFromPathError> for EeStatus {
+    fn from(error: PollTableTryFromPathError) -> Self {
+        match error {
+            PollTableTryFromPathError::ReaderBuilderError(_) => EeStatus::CsvError,
+            PollTableTryFromPathError::NotCsvError => EeStatus::NotCsv,
+            PollTableTryFromPathError::InvalidPathError => EeStatus::InvalidPath,
+            PollTableTryFromPathError::InvalidJurisdictionError => EeStatus::InvalidJurisdiction,
+        }
+    }
+}
+
+impl From<PollTableFromStrError> for EeStatus {
+    fn from(error: PollTableFromStrError) -> Self {
+        match error {
+            PollTableFromStrError::ReaderBuilderError(_) => EeStatus::CsvError,
+            PollTableFromStrError::InvalidJurisdictionError => EeStatus::InvalidJurisdiction,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+/// A calendar date.
+pub struct EeDate {
+    /// The year, such as 2024.
+    pub year: i32,
+    /// The month, from 1 to 12.
+    pub month: u32,
+    /// The day of the month, from 1 to 31.
+    pub day: u32,
+}
+
+impl From<NaiveDate> for EeDate {
+    fn from(date: NaiveDate) -> Self {
+        EeDate {
+            year: date.year(),
+            month: date.month(),
+            day: date.day(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+/// A party result. When `available` is 0, the other fields are meaningless.
+pub struct EeValue {
+    /// The result, in percent or seats.
+    pub value: f64,
+    /// 1 if the result is a number of seats, 0 if it is a percentage.
+    pub is_seats: u8,
+    /// 1 if the poll gives a result, 0 if it is "Not Available" or the party is not in the poll.
+    pub available: u8,
+}
+
+impl EeValue {
+    fn new(result: Option<&PollOption<PercentageOrSeats>>) -> Self {
+        match result {
+            Some(PollOption::Some(result)) => EeValue {
+                value: result.value() as f64,
+                is_seats: matches!(result, PercentageOrSeats::Seats(_)) as u8,
+                available: 1,
+            },
+            _ => EeValue::default(),
+        }
+    }
+}
+
+/// An opaque handle to a [PollTable], with C strings of its text fields.
+pub struct EePollTable {
+    table: PollTable,
+    jurisdiction: CString,
+    parties: Vec<String>,
+    party_names: Vec<CString>,
+    polling_firms: Vec<CString>,
+    commissioners: Vec<Option<CString>>,
+}
+
+/// Converts text to a C string, dropping any interior NUL bytes.
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).expect("interior NUL bytes were removed")
+}
+
+impl EePollTable {
+    fn new(table: PollTable) -> Self {
+        let mut parties: Vec<String> = table
+            .polls
+            .iter()
+            .flat_map(|poll| poll.party_results.keys().cloned())
+            .collect();
+        parties.sort();
+        parties.dedup();
+        EePollTable {
+            jurisdiction: c_string(table.jurisdiction.code()),
+            party_names: parties.iter().map(|party| c_string(party)).collect(),
+            parties,
+            polling_firms: table
+                .polls
+                .iter()
+                .map(|poll| c_string(&poll.polling_firm))
+                .collect(),
+            commissioners: table
+                .polls
+                .iter()
+                .map(|poll| match &poll.commissioners {
+                    PollOption::Some(commissioners) => Some(c_string(commissioners)),
+                    PollOption::NotAvailable => None,
+                })
+                .collect(),
+            table,
+        }
+    }
+}
+
+/// Runs the body of an exported function, returning `fallback` if it panics instead of unwinding into the caller.
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    std::panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+/// Reads a C string argument.
+///
+/// # Safety
+/// `s` must be null or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, EeStatus> {
+    if s.is_null() {
+        return Err(EeStatus::NullPointer);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| EeStatus::InvalidUtf8)
+}
+
+/// Stores a new handle in `out`.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+unsafe fn create(table: PollTable, out: *mut *mut EePollTable) -> EeStatus {
+    if out.is_null() {
+        return EeStatus::NullPointer;
+    }
+    *out = Box::into_raw(Box::new(EePollTable::new(table)));
+    EeStatus::Ok
+}
+
+/// Returns the poll at an index of a table handle.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed.
+unsafe fn poll_at<'a>(
+    table: *const EePollTable,
+    poll: usize,
+) -> Result<(&'a EePollTable, &'a crate::Poll), EeStatus> {
+    let table = table.as_ref().ok_or(EeStatus::NullPointer)?;
+    let poll = table
+        .table
+        .polls
+        .get(poll)
+        .ok_or(EeStatus::IndexOutOfRange)?;
+    Ok((table, poll))
+}
+
+/// Stores a value in an out parameter, or fails if the pointer is null.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+unsafe fn write<T>(out: *mut T, value: T) -> EeStatus {
+    match out.as_mut() {
+        Some(out) => {
+            *out = value;
+            EeStatus::Ok
+        }
+        None => EeStatus::NullPointer,
+    }
+}
+
+/// Loads a Europe Elects .csv file, whose name gives the jurisdiction.
+///
+/// # Safety
+/// `path` must be a NUL-terminated string, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_from_path(
+    path: *const c_char,
+    out: *mut *mut EePollTable,
+) -> EeStatus {
+    guard(EeStatus::Panic, || {
+        let path = match str_arg(path) {
+            Ok(path) => path,
+            Err(status) => return status,
+        };
+        match PollTable::try_from_path(path) {
+            Ok(table) => create(table, out),
+            Err(error) => error.into(),
+        }
+    })
+}
+
+/// Parses `len` bytes of Europe Elects .csv data for a jurisdiction code, such as "de".
+///
+/// # Safety
+/// `data` must be valid for reads of `len` bytes, `jurisdiction` a NUL-terminated string, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_from_buffer(
+    data: *const u8,
+    len: usize,
+    jurisdiction: *const c_char,
+    out: *mut *mut EePollTable,
+) -> EeStatus {
+    guard(EeStatus::Panic, || {
+        if data.is_null() {
+            return EeStatus::NullPointer;
+        }
+        let jurisdiction = match str_arg(jurisdiction) {
+            Ok(jurisdiction) => jurisdiction,
+            Err(status) => return status,
+        };
+        let data = std::slice::from_raw_parts(data, len);
+        match PollTable::from_reader(data, jurisdiction) {
+            Ok(table) => create(table, out),
+            Err(error) => error.into(),
+        }
+    })
+}
+
+/// Frees a table handle. Does nothing if `table` is null.
+///
+/// # Safety
+/// `table` must be null or a handle that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_free(table: *mut EePollTable) {
+    guard((), || {
+        if !table.is_null() {
+            drop(Box::from_raw(table));
+        }
+    })
+}
+
+/// Returns the jurisdiction code of a table, such as "de", or null if `table` is null.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_jurisdiction(table: *const EePollTable) -> *const c_char {
+    guard(std::ptr::null(), || {
+        table
+            .as_ref()
+            .map_or(std::ptr::null(), |table| table.jurisdiction.as_ptr())
+    })
+}
+
+/// Returns the number of polls in a table, or 0 if `table` is null.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_poll_count(table: *const EePollTable) -> usize {
+    guard(0, || {
+        table.as_ref().map_or(0, |table| table.table.polls.len())
+    })
+}
+
+/// Returns the number of party columns in a table, or 0 if `table` is null.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_party_count(table: *const EePollTable) -> usize {
+    guard(0, || table.as_ref().map_or(0, |table| table.parties.len()))
+}
+
+/// Returns the name of a party column, in alphabetical order, or null if out of range.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_table_party_name(
+    table: *const EePollTable,
+    party: usize,
+) -> *const c_char {
+    guard(std::ptr::null(), || {
+        table
+            .as_ref()
+            .and_then(|table| table.party_names.get(party))
+            .map_or(std::ptr::null(), |name| name.as_ptr())
+    })
+}
+
+/// Gets the polling firm of a poll, where poll 0 is the newest.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_polling_firm(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut *const c_char,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((table, _)) => write(out, table.polling_firms[poll].as_ptr()),
+        Err(status) => status,
+    })
+}
+
+/// Gets the commissioners of a poll, or returns `EE_NOT_AVAILABLE`.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_commissioners(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut *const c_char,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((table, _)) => match &table.commissioners[poll] {
+            Some(commissioners) => write(out, commissioners.as_ptr()),
+            None => EeStatus::NotAvailable,
+        },
+        Err(status) => status,
+    })
+}
+
+/// Gets the first day of fieldwork of a poll.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_fieldwork_start(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut EeDate,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((_, poll)) => write(out, poll.fieldwork_start.into()),
+        Err(status) => status,
+    })
+}
+
+/// Gets the last day of fieldwork of a poll.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_fieldwork_end(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut EeDate,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((_, poll)) => write(out, poll.fieldwork_end.into()),
+        Err(status) => status,
+    })
+}
+
+/// Gets whether a poll is for a national (0) or European (1) election.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_scope(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut u8,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((_, poll)) => write(out, (poll.scope == Scope::European) as u8),
+        Err(status) => status,
+    })
+}
+
+/// Gets the sample size of a poll, or returns `EE_NOT_AVAILABLE`.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_sample_size(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut f64,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((_, poll)) => match poll.sample_size {
+            PollOption::Some(size) => write(out, size as f64),
+            PollOption::NotAvailable => EeStatus::NotAvailable,
+        },
+        Err(status) => status,
+    })
+}
+
+/// Gets the result of a party column, as numbered by `ee_poll_table_party_name`, in a poll.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_party_value(
+    table: *const EePollTable,
+    poll: usize,
+    party: usize,
+    out: *mut EeValue,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((table, poll)) => match table.parties.get(party) {
+            Some(party) => write(out, EeValue::new(poll.party_results.get(party))),
+            None => EeStatus::IndexOutOfRange,
+        },
+        Err(status) => status,
+    })
+}
+
+/// Gets the result of the "Other" column in a poll.
+///
+/// # Safety
+/// `table` must be null or a handle that has not been freed, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ee_poll_other(
+    table: *const EePollTable,
+    poll: usize,
+    out: *mut EeValue,
+) -> EeStatus {
+    guard(EeStatus::Panic, || match poll_at(table, poll) {
+        Ok((_, poll)) => write(out, EeValue::new(Some(&poll.other))),
+        Err(status) => status,
+    })
+}
+
+/// Descriptions of every [EeStatus], in order.
+const STATUS_MESSAGES: [&CStr; 10] = [
+    c"ok",
+    c"a required pointer was null",
+    c"a string argument was not valid UTF-8",
+    c"failed to read or parse .csv data",
+    c"specified file is not a .csv",
+    c"specified path is not valid",
+    c"not a valid Europe Elects jurisdiction",
+    c"index out of range",
+    c"not available",
+    c"internal error",
+];
+
+/// Returns a static description of a status code.
+#[no_mangle]
+pub extern "C" fn ee_status_message(status: c_int) -> *const c_char {
+    guard(c"unknown status".as_ptr(), || {
+        usize::try_from(status)
+            .ok()
+            .and_then(|status| STATUS_MESSAGES.get(status))
+            .map_or(c"unknown status", |message| *message)
+            .as_ptr()
+    })
+}
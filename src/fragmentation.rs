@@ -0,0 +1,188 @@
+//! Fragmentation and competitiveness indices of the party system.
+use crate::average::PollAverage;
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+/// How the combined share of other parties enters the concentration indices.
+pub enum OtherParties {
+    /// Other parties are assumed to be too small to matter, and add nothing to the Herfindahl index.
+    /// This gives the highest possible effective number of parties.
+    #[default]
+    Fragmented,
+    /// Other parties are treated as one party. This gives the lowest possible effective number of parties.
+    SingleParty,
+    /// Other parties are left out, and the shares of the named parties are rescaled to add up to 100%.
+    Excluded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+/// Whether an index was computed from vote shares or from seats.
+pub enum Basis {
+    /// Vote shares, giving the effective number of electoral parties.
+    Votes,
+    /// Seats, giving the effective number of parliamentary parties.
+    Seats,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+/// Fragmentation and competitiveness indices of one set of results.
+pub struct Fragmentation {
+    basis: Basis,
+    herfindahl: f32,
+    effective_parties: f32,
+    lead: Option<f32>,
+    top_two: Option<f32>,
+}
+
+impl Fragmentation {
+    /// Computes the indices from party shares in percent and the combined share of other parties.
+    fn new(
+        basis: Basis,
+        mut shares: Vec<f32>,
+        other: f32,
+        treatment: OtherParties,
+    ) -> Option<Self> {
+        shares.sort_by(|a, b| b.total_cmp(a));
+        let named: f32 = shares.iter().sum();
+        let (total, other) = match treatment {
+            OtherParties::Fragmented => (named + other, 0.0),
+            OtherParties::SingleParty => (named + other, other),
+            OtherParties::Excluded => (named, 0.0),
+        };
+        if shares.is_empty() || total <= 0.0 {
+            return None;
+        }
+        let herfindahl = shares
+            .iter()
+            .chain(std::iter::once(&other))
+            .map(|share| (share / total).powi(2))
+            .sum::<f32>();
+        let scale = 100.0 / total;
+        Some(Fragmentation {
+            basis,
+            herfindahl,
+            effective_parties: 1.0 / herfindahl,
+            lead: (shares.len() >= 2).then(|| (shares[0] - shares[1]) * scale),
+            top_two: (shares.len() >= 2).then(|| (shares[0] + shares[1]) * scale),
+        })
+    }
+
+    /// Returns whether the indices were computed from vote shares or from seats.
+    pub fn basis(&self) -> Basis {
+        self.basis
+    }
+
+    /// Returns the Herfindahl concentration index, the sum of the squared shares as fractions, from 0 to 1.
+    pub fn herfindahl(&self) -> f32 {
+        self.herfindahl
+    }
+
+    /// Returns the Laakso–Taagepera effective number of parties, the inverse of the Herfindahl index.
+    pub fn effective_parties(&self) -> f32 {
+        self.effective_parties
+    }
+
+    /// Returns the lead of the largest party over the second largest, in percentage points.
+    pub fn lead(&self) -> Option<f32> {
+        self.lead
+    }
+
+    /// Returns the combined share of the two largest parties, in percent.
+    pub fn top_two(&self) -> Option<f32> {
+        self.top_two
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+/// The indices of one poll, as returned by [PollTable::fragmentation_series].
+pub struct FragmentationPoint {
+    /// The index of the poll in its [PollTable].
+    pub index: usize,
+    /// The last day of fieldwork.
+    pub date: NaiveDate,
+    /// The indices of the poll.
+    pub fragmentation: Fragmentation,
+}
+
+impl Poll {
+    /// Computes the fragmentation indices of the poll.
+    ///
+    /// Parties whose result is not available are left out. Polls of seats are measured by seats,
+    /// giving the effective number of parliamentary parties. Returns [None] if the poll has no results.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::fragmentation::OtherParties;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Third Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,40%,30%,Not Available,30%
+    /// Seat Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1,50,50,Not Available,0";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let votes = poll_table.poll_by_index(0).unwrap().fragmentation(OtherParties::Fragmented).unwrap();
+    /// let seats = poll_table.poll_by_index(1).unwrap().fragmentation(OtherParties::Fragmented).unwrap();
+    ///
+    /// assert!((votes.herfindahl() - 0.25).abs() < 1e-6);
+    /// assert!((votes.effective_parties() - 4.0).abs() < 1e-4);
+    /// assert_eq!(votes.lead(), Some(10.0));
+    /// assert_eq!(votes.top_two(), Some(70.0));
+    /// assert_eq!(seats.effective_parties(), 2.0);
+    /// ```
+    pub fn fragmentation(&self, other: OtherParties) -> Option<Fragmentation> {
+        let percentages: Vec<f32> = self
+            .party_results
+            .values()
+            .filter_map(|result| match result {
+                PollOption::Some(PercentageOrSeats::Percentage(share)) => Some(share.value()),
+                _ => None,
+            })
+            .collect();
+        let (basis, shares) = if percentages.is_empty() {
+            let seats = self
+                .party_results
+                .values()
+                .filter_map(|result| match result {
+                    PollOption::Some(PercentageOrSeats::Seats(seats)) => Some(seats.value()),
+                    _ => None,
+                })
+                .collect();
+            (Basis::Seats, seats)
+        } else {
+            (Basis::Votes, percentages)
+        };
+        let other_share = match (&basis, &self.other) {
+            (Basis::Votes, PollOption::Some(PercentageOrSeats::Percentage(share))) => share.value(),
+            (Basis::Seats, PollOption::Some(PercentageOrSeats::Seats(seats))) => seats.value(),
+            _ => 0.0,
+        };
+        Fragmentation::new(basis, shares, other_share, other)
+    }
+}
+
+impl PollAverage {
+    /// Computes the fragmentation indices of the average, treating the share not covered by any party as other parties.
+    pub fn fragmentation(&self, other: OtherParties) -> Option<Fragmentation> {
+        let shares: Vec<f32> = self.parties().values().map(|party| party.value()).collect();
+        let rest = (100.0 - shares.iter().sum::<f32>()).max(0.0);
+        Fragmentation::new(Basis::Votes, shares, rest, other)
+    }
+}
+
+impl PollTable {
+    /// Computes the fragmentation indices of every poll, ordered by the end of fieldwork from oldest to newest.
+    pub fn fragmentation_series(&self, other: OtherParties) -> Vec<FragmentationPoint> {
+        let mut series: Vec<FragmentationPoint> = self
+            .polls
+            .iter()
+            .enumerate()
+            .filter_map(|(index, poll)| {
+                Some(FragmentationPoint {
+                    index,
+                    date: poll.fieldwork_end,
+                    fragmentation: poll.fragmentation(other)?,
+                })
+            })
+            .collect();
+        series.sort_by_key(|point| (point.date, point.index));
+        series
+    }
+}
@@ -0,0 +1,153 @@
+//! Stable identifiers and content hashes for polls.
+use crate::{Poll, PollTable};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Separates fields in the hashed representation of a poll, so that "ab" + "c" and "a" + "bc" hash differently.
+const SEPARATOR: u8 = 0x1f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A deterministic identifier for a poll, derived from its polling firm, commissioners, fieldwork dates and scope.
+///
+/// Ids stay the same across reloads and corrections of a poll's results, and are written as 16 hexadecimal digits.
+pub struct PollId(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A hash of every field of a poll, including all results, which changes whenever the poll is corrected.
+pub struct ContentHash(u64);
+
+impl PollId {
+    /// Returns the id as an integer.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl ContentHash {
+    /// Returns the hash as an integer.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for PollId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for PollId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<PollId, Self::Err> {
+        u64::from_str_radix(s, 16).map(PollId)
+    }
+}
+
+impl Serialize for PollId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for ContentHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is guaranteed to be stable across Rust versions.
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Fnv(0xcbf29ce484222325)
+    }
+
+    pub(crate) fn field(&mut self, field: &str) -> &mut Self {
+        for byte in field.bytes().chain(std::iter::once(SEPARATOR)) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+        self
+    }
+}
+
+impl Poll {
+    /// Returns the [PollId] of the poll.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// let old = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,21%,79%";
+    /// let new = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,22%,78%";
+    /// let old = PollTable::from_str(old, "de").unwrap();
+    /// let new = PollTable::from_str(new, "de").unwrap();
+    /// let (old_poll, new_poll) = (old.poll_by_index(0).unwrap(), new.poll_by_index(0).unwrap());
+    ///
+    /// assert_eq!(old_poll.id(), new_poll.id());
+    /// assert_ne!(old_poll.content_hash(), new_poll.content_hash());
+    /// assert_eq!(new.poll_by_id(&new_poll.id()).unwrap().id(), new_poll.id());
+    /// assert!(std::ptr::eq(new.id_index(), new.id_index()));
+    /// ```
+    pub fn id(&self) -> PollId {
+        let mut hasher = Fnv::new();
+        hasher
+            .field(&self.polling_firm)
+            .field(&self.commissioners.to_string())
+            .field(&self.fieldwork_start.to_string())
+            .field(&self.fieldwork_end.to_string())
+            .field(&self.scope.to_string());
+        PollId(hasher.0)
+    }
+
+    /// Returns the [ContentHash] of the poll. Party columns are hashed in alphabetical order, so the order of columns in a file does not matter.
+    pub fn content_hash(&self) -> ContentHash {
+        let mut hasher = Fnv::new();
+        hasher
+            .field(&self.id().to_string())
+            .field(&self.sample_size.to_string())
+            .field(&self.sample_size_qualification.to_string())
+            .field(&self.participation.to_string())
+            .field(&self.precision.to_string());
+        let mut parties: Vec<(&String, String)> = self
+            .party_results
+            .iter()
+            .map(|(party, result)| (party, result.to_string()))
+            .collect();
+        parties.sort();
+        for (party, result) in parties {
+            hasher.field(party).field(&result);
+        }
+        hasher.field(&self.other.to_string());
+        ContentHash(hasher.0)
+    }
+}
+
+impl PollTable {
+    /// Returns an index from [PollId] to the index of the poll in the [PollTable].
+    /// If several polls share an id, the first one is indexed; see [PollTable::validate].
+    /// The index is built on the first call and kept with the table.
+    pub fn id_index(&self) -> &HashMap<PollId, usize> {
+        self.ids.get_or_init(|| {
+            let mut index = HashMap::with_capacity(self.polls.len());
+            for (i, poll) in self.polls.iter().enumerate() {
+                index.entry(poll.id()).or_insert(i);
+            }
+            index
+        })
+    }
+
+    /// Returns the first poll with the given [PollId], using [PollTable::id_index].
+    pub fn poll_by_id(&self, id: &PollId) -> Option<&Poll> {
+        self.id_index().get(id).map(|&i| &self.polls[i])
+    }
+}
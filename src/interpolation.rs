@@ -0,0 +1,245 @@
+//! Estimates of party support at an arbitrary date that only use polls published by then.
+use crate::average::{AverageOptions, PartyAverage, PollAverage};
+use crate::{PercentageOrSeats, PollOption, PollTable, Scope};
+use chrono::{Days, NaiveDate};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How [PollTable::as_of] turns the published polls into an estimate.
+pub enum Interpolation {
+    /// The result of the most recently published poll with a result for the party.
+    LastValue,
+    /// A straight line through the rolling averages of [PollTable::average_at] at the two most recent poll dates, extended to the date.
+    /// With a single poll date, this is the average at that date.
+    Linear,
+    /// A straight line fitted through the polls in the window, read off at the date.
+    /// Unlike an average, this follows a trend up to the date, and can extrapolate beyond the latest poll.
+    Trend,
+    /// The weighted rolling average of [PollTable::average_at].
+    #[default]
+    Model,
+}
+
+#[derive(Debug, Clone)]
+/// Settings for [PollTable::as_of].
+pub struct AsOfOptions {
+    interpolation: Interpolation,
+    window_days: i64,
+    publication_lag_days: u64,
+    scope: Scope,
+}
+
+impl Default for AsOfOptions {
+    fn default() -> Self {
+        AsOfOptions::new()
+    }
+}
+
+impl AsOfOptions {
+    /// Creates options for the model-based estimate of national polls over a window of 28 days, assuming polls are published when their fieldwork ends.
+    pub fn new() -> Self {
+        AsOfOptions {
+            interpolation: Interpolation::default(),
+            window_days: 28,
+            publication_lag_days: 0,
+            scope: Scope::National,
+        }
+    }
+
+    /// Sets the interpolation method.
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Sets how many days before the date polls are used by the linear, trend and model-based methods.
+    pub fn window_days(mut self, window_days: i64) -> Self {
+        self.window_days = window_days;
+        self
+    }
+
+    /// Sets how many days after the end of fieldwork a poll is assumed to be published.
+    /// The poll files do not record publication dates, so this is the only way to account for them.
+    pub fn publication_lag_days(mut self, publication_lag_days: u64) -> Self {
+        self.publication_lag_days = publication_lag_days;
+        self
+    }
+
+    /// Sets the scope of the polls used, as in [AverageOptions::scope].
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+}
+
+impl PollTable {
+    /// Returns the polls that were published by a date, assuming publication `lag_days` after the end of fieldwork.
+    pub fn published_by(&self, date: &NaiveDate, lag_days: u64) -> PollTable {
+        PollTable::new(
+            self.polls
+                .iter()
+                .filter(|poll| poll.fieldwork_end + Days::new(lag_days) <= *date)
+                .cloned()
+                .collect(),
+            self.jurisdiction,
+        )
+    }
+
+    /// Estimates each party's support at a date.
+    ///
+    /// Only polls published by the date are used, so estimates for past dates are the same as they would have been on that date.
+    /// Only results given as percentages, in polls of the options' scope, are used.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::interpolation::{AsOfOptions, Interpolation};
+    /// use chrono::NaiveDate;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2023-10-01,2023-10-01,National,1000,Provided,Not Available,1%,20%,80%
+    /// Other Polling,Not Available,2023-10-11,2023-10-11,National,1000,Provided,Not Available,1%,22%,78%
+    /// Epic Polling,Not Available,2023-10-12,2023-10-12,European,1000,Provided,Not Available,1%,50%,50%
+    /// Late Polling,Not Available,2023-10-14,2023-10-16,National,1000,Provided,Not Available,1%,40%,60%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+    /// let last = poll_table.as_of(&date, &AsOfOptions::new().interpolation(Interpolation::LastValue));
+    /// let linear = poll_table.as_of(&date, &AsOfOptions::new().interpolation(Interpolation::Linear));
+    /// let trend = poll_table.as_of(&date, &AsOfOptions::new().interpolation(Interpolation::Trend));
+    /// let model = poll_table.as_of(&date, &AsOfOptions::new());
+    ///
+    /// assert_eq!(last.value("First Party"), Some(22.0));
+    /// assert!((linear.value("First Party").unwrap() - 21.69).abs() < 0.01);
+    /// assert!((trend.value("First Party").unwrap() - 22.8).abs() < 0.01);
+    /// assert!((model.value("First Party").unwrap() - 21.25).abs() < 0.01);
+    /// ```
+    pub fn as_of(&self, date: &NaiveDate, options: &AsOfOptions) -> PollAverage {
+        let published = self.published_by(date, options.publication_lag_days);
+        let average_options = AverageOptions::new()
+            .window_days(options.window_days)
+            .scope(options.scope);
+        match options.interpolation {
+            Interpolation::Model => published.average_at(date, &average_options),
+            Interpolation::LastValue => published.last_values(date, options.scope),
+            Interpolation::Linear => {
+                published.linear_between_averages(date, options.scope, &average_options)
+            }
+            Interpolation::Trend => published.linear_trend(date, &average_options),
+        }
+    }
+
+    fn last_values(&self, date: &NaiveDate, scope: Scope) -> PollAverage {
+        let mut latest: BTreeMap<&String, (NaiveDate, f32, f32)> = BTreeMap::new();
+        for poll in self.polls.iter().filter(|poll| poll.scope == scope) {
+            let sample_size = poll.effective_sample_size();
+            for (party, result) in &poll.party_results {
+                if let PollOption::Some(PercentageOrSeats::Percentage(share)) = result {
+                    if latest
+                        .get(party)
+                        .is_none_or(|(end, _, _)| *end <= poll.fieldwork_end)
+                    {
+                        let p = share.value() / 100.0;
+                        let std_dev = (p * (1.0 - p) / sample_size).sqrt() * 100.0;
+                        latest.insert(party, (poll.fieldwork_end, share.value(), std_dev));
+                    }
+                }
+            }
+        }
+        let parties = latest
+            .into_iter()
+            .map(|(party, (_, value, std_dev))| {
+                (party.clone(), PartyAverage::new(value, std_dev, 1))
+            })
+            .collect();
+        PollAverage::new(*date, parties)
+    }
+
+    fn linear_between_averages(
+        &self,
+        date: &NaiveDate,
+        scope: Scope,
+        options: &AverageOptions,
+    ) -> PollAverage {
+        let mut ends: Vec<NaiveDate> = self
+            .polls
+            .iter()
+            .filter(|poll| poll.scope == scope)
+            .map(|poll| poll.fieldwork_end)
+            .collect();
+        ends.sort();
+        ends.dedup();
+        let parties = match ends.as_slice() {
+            [.., previous, latest] => {
+                let start = self.average_at(previous, options);
+                let end = self.average_at(latest, options);
+                // Days past the latest poll date, in units of the gap between the two poll dates.
+                let t =
+                    (*date - *latest).num_days() as f32 / (*latest - *previous).num_days() as f32;
+                end.parties()
+                    .iter()
+                    .map(|(party, b)| {
+                        let average = match start.party(party) {
+                            Some(a) => PartyAverage::new(
+                                (b.value() + t * (b.value() - a.value())).max(0.0),
+                                b.std_dev(),
+                                b.polls(),
+                            ),
+                            None => *b,
+                        };
+                        (party.clone(), average)
+                    })
+                    .collect()
+            }
+            [latest] => self.average_at(latest, options).parties().clone(),
+            [] => BTreeMap::new(),
+        };
+        PollAverage::new(*date, parties)
+    }
+
+    fn linear_trend(&self, date: &NaiveDate, options: &AverageOptions) -> PollAverage {
+        // Each observation is (days from the date to the fieldwork midpoint, result, weight).
+        let mut observations: BTreeMap<&String, Vec<(f32, f32, f32)>> = BTreeMap::new();
+        for poll in &self.polls {
+            let Some(weight) = options.weight(poll, date) else {
+                continue;
+            };
+            let x = (poll.fieldwork_midpoint() - *date).num_days() as f32;
+            for (party, result) in &poll.party_results {
+                if let PollOption::Some(PercentageOrSeats::Percentage(share)) = result {
+                    observations
+                        .entry(party)
+                        .or_default()
+                        .push((x, share.value(), weight));
+                }
+            }
+        }
+
+        let parties = observations
+            .into_iter()
+            .map(|(party, points)| {
+                let total: f32 = points.iter().map(|(_, _, w)| w).sum();
+                let mean_x = points.iter().map(|(x, _, w)| x * w).sum::<f32>() / total;
+                let mean_y = points.iter().map(|(_, y, w)| y * w).sum::<f32>() / total;
+                let spread: f32 = points
+                    .iter()
+                    .map(|(x, _, w)| w * (x - mean_x).powi(2))
+                    .sum();
+                let slope = if spread > 0.0 {
+                    points
+                        .iter()
+                        .map(|(x, y, w)| w * (x - mean_x) * (y - mean_y))
+                        .sum::<f32>()
+                        / spread
+                } else {
+                    0.0
+                };
+                let intercept = mean_y - slope * mean_x;
+                let variance = points
+                    .iter()
+                    .map(|(x, y, w)| w * (y - intercept - slope * x).powi(2))
+                    .sum::<f32>()
+                    / total;
+                let average = PartyAverage::new(intercept, variance.sqrt(), points.len());
+                (party.clone(), average)
+            })
+            .collect();
+        PollAverage::new(*date, parties)
+    }
+}
@@ -13,14 +13,56 @@
 //! assert_eq!(british_data.jurisdiction(), "United Kingdom of Great Britain and Northern Ireland");
 //! assert_eq!(british_data.date_range(), 2252);
 //! ```
+pub mod accuracy;
+pub mod alerts;
+pub mod average;
+pub mod blocs;
+pub mod changes;
+#[cfg(feature = "chart")]
+pub mod chart;
+pub mod coalitions;
+pub mod collection;
+pub mod diff;
+pub mod duplicates;
+pub mod elections;
 mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fragmentation;
+pub mod identity;
+pub mod interpolation;
+pub mod outliers;
+pub mod party;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+pub mod resample;
+pub mod seats;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod simulation;
+pub mod social;
+#[cfg(feature = "xlsx")]
+pub mod spreadsheet;
+pub mod swing;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod validation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watch;
+pub mod wikitext;
 use chrono::NaiveDate;
 use csv::ReaderBuilder;
 use errors::{PollTableFromStrError, PollTableTryFromPathError};
 use serde::{Deserialize, Deserializer};
-use std::{collections::HashMap, path::Path};
+use identity::PollId;
+use std::{collections::HashMap, fmt, path::Path, sync::OnceLock};
 
-#[derive(Copy, Clone, Debug)]
+/// The sample size assumed for polls that do not provide one when estimating sampling error.
+pub const DEFAULT_SAMPLE_SIZE: f32 = 1000.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 /// The countries, regions and territories for which Europe Elects collects opinion poll data.
 pub enum Jurisdiction {
     Albania,
@@ -74,6 +116,134 @@ pub enum Jurisdiction {
     Ukraine,
 }
 
+impl Jurisdiction {
+    /// Returns the code of the jurisdiction, as used in the Europe Elects .csv filenames.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Jurisdiction::Albania => "al",
+            Jurisdiction::Andorra => "ad",
+            Jurisdiction::Armenia => "am",
+            Jurisdiction::Austria => "at",
+            Jurisdiction::BelgiumBrussels => "be-bru",
+            Jurisdiction::BelgiumFlanders => "be-vlg",
+            Jurisdiction::BelgiumWallonia => "be-wal",
+            Jurisdiction::Bulgaria => "bg",
+            Jurisdiction::Croatia => "hr",
+            Jurisdiction::Cyprus => "cy",
+            Jurisdiction::Czechia => "cz",
+            Jurisdiction::Denmark => "dk",
+            Jurisdiction::Estonia => "ee",
+            Jurisdiction::Finland => "fi",
+            Jurisdiction::France => "fr",
+            Jurisdiction::Georgia => "ge",
+            Jurisdiction::Germany => "de",
+            Jurisdiction::Gibraltar => "gi",
+            Jurisdiction::Greece => "gr",
+            Jurisdiction::Hungary => "hu",
+            Jurisdiction::Iceland => "is",
+            Jurisdiction::Ireland => "ie",
+            Jurisdiction::Italy => "it",
+            Jurisdiction::Kosovo => "xk",
+            Jurisdiction::Latvia => "lv",
+            Jurisdiction::Lithuania => "lt",
+            Jurisdiction::Luxembourg => "lu",
+            Jurisdiction::Malta => "mt",
+            Jurisdiction::Moldova => "md",
+            Jurisdiction::Montenegro => "me",
+            Jurisdiction::Netherlands => "nl",
+            Jurisdiction::NorthMacedonia => "mk",
+            Jurisdiction::Norway => "no",
+            Jurisdiction::Poland => "pl",
+            Jurisdiction::Portugal => "pt",
+            Jurisdiction::Romania => "ro",
+            Jurisdiction::Russia => "ru",
+            Jurisdiction::Serbia => "rs",
+            Jurisdiction::Slovakia => "sk",
+            Jurisdiction::Slovenia => "si",
+            Jurisdiction::Spain => "es",
+            Jurisdiction::Sweden => "se",
+            Jurisdiction::Switzerland => "ch",
+            Jurisdiction::Turkiye => "tr",
+            Jurisdiction::UKGreatBritain => "gb",
+            Jurisdiction::UKNorthernIreland => "gb-nir",
+            Jurisdiction::UKNorthernIrelandEuropean => "gb-nir-E",
+            Jurisdiction::UKNorthernIrelandNational => "gb-nir-N",
+            Jurisdiction::Ukraine => "ua",
+        }
+    }
+
+    /// Returns the English name of the jurisdiction.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Jurisdiction::Albania => "Albania",
+            Jurisdiction::Andorra => "Andorra",
+            Jurisdiction::Armenia => "Armenia",
+            Jurisdiction::Austria => "Austria",
+            Jurisdiction::BelgiumBrussels => "Belgium (Brussels)",
+            Jurisdiction::BelgiumFlanders => "Belgium (Flanders)",
+            Jurisdiction::BelgiumWallonia => "Belgium (Wallonia)",
+            Jurisdiction::Bulgaria => "Bulgaria",
+            Jurisdiction::Croatia => "Croatia",
+            Jurisdiction::Cyprus => "Cyprus",
+            Jurisdiction::Czechia => "Czechia",
+            Jurisdiction::Denmark => "Denmark",
+            Jurisdiction::Estonia => "Estonia",
+            Jurisdiction::Finland => "Finland",
+            Jurisdiction::France => "France",
+            Jurisdiction::Georgia => "Georgia",
+            Jurisdiction::Germany => "Germany",
+            Jurisdiction::Gibraltar => "Gibraltar",
+            Jurisdiction::Greece => "Greece",
+            Jurisdiction::Hungary => "Hungary",
+            Jurisdiction::Iceland => "Iceland",
+            Jurisdiction::Ireland => "Ireland",
+            Jurisdiction::Italy => "Italy",
+            Jurisdiction::Kosovo => "Kosovo",
+            Jurisdiction::Latvia => "Latvia",
+            Jurisdiction::Lithuania => "Lithuania",
+            Jurisdiction::Luxembourg => "Luxembourg",
+            Jurisdiction::Malta => "Malta",
+            Jurisdiction::Moldova => "Moldova",
+            Jurisdiction::Montenegro => "Montenegro",
+            Jurisdiction::Netherlands => "Netherlands",
+            Jurisdiction::NorthMacedonia => "North Macedonia",
+            Jurisdiction::Norway => "Norway",
+            Jurisdiction::Poland => "Poland",
+            Jurisdiction::Portugal => "Portugal",
+            Jurisdiction::Romania => "Romania",
+            Jurisdiction::Russia => "Russia",
+            Jurisdiction::Serbia => "Serbia",
+            Jurisdiction::Slovakia => "Slovakia",
+            Jurisdiction::Slovenia => "Slovenia",
+            Jurisdiction::Spain => "Spain",
+            Jurisdiction::Sweden => "Sweden",
+            Jurisdiction::Switzerland => "Switzerland",
+            Jurisdiction::Turkiye => "Türkiye",
+            Jurisdiction::UKGreatBritain => "UK (Great Britain)",
+            Jurisdiction::UKNorthernIreland => "UK (Northern Ireland)",
+            Jurisdiction::UKNorthernIrelandEuropean => "UK (Northern Ireland)",
+            Jurisdiction::UKNorthernIrelandNational => "UK (Northern Ireland)",
+            Jurisdiction::Ukraine => "Ukraine",
+        }
+    }
+
+    /// Returns the flag emoji of the country the jurisdiction belongs to.
+    /// ```
+    /// use europe_elects_csv::Jurisdiction;
+    ///
+    /// assert_eq!(Jurisdiction::Germany.flag(), "🇩🇪");
+    /// assert_eq!(Jurisdiction::BelgiumFlanders.flag(), "🇧🇪");
+    /// ```
+    pub fn flag(&self) -> String {
+        // Regional indicator symbols start at U+1F1E6, which stands for "A".
+        let country = self.code().split('-').next().unwrap_or_default();
+        country
+            .chars()
+            .filter_map(|c| char::from_u32(0x1F1E6 + c.to_ascii_lowercase() as u32 - 'a' as u32))
+            .collect()
+    }
+}
+
 fn init_jurisdiction() -> HashMap<String, Jurisdiction> {
     HashMap::from([
         (String::from("al"), Jurisdiction::Albania),
@@ -133,12 +303,14 @@ fn init_jurisdiction() -> HashMap<String, Jurisdiction> {
         (String::from("ua"), Jurisdiction::Ukraine),
     ])
 }
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Represents one EuropeElects .csv file.
 /// It contains metadata about the particular poll file, and the individual opinion polls themselves.
 pub struct PollTable {
     polls: Vec<Poll>,
     jurisdiction: Jurisdiction,
+    /// The index of [PollTable::id_index], built on first use.
+    ids: OnceLock<HashMap<PollId, usize>>,
 }
 
 #[derive(Debug)]
@@ -148,7 +320,7 @@ pub struct RawPollTable {
 }
 
 /// Each Poll is one line of .csv, and represents all metadata and party results for one opinion poll.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Poll {
     #[serde(rename = "Polling Firm")]
     polling_firm: String,
@@ -179,6 +351,7 @@ impl PollTable {
         PollTable {
             polls,
             jurisdiction,
+            ids: OnceLock::new(),
         }
     }
     /// Attempts to create a [PollTable] from a .csv file.
@@ -241,10 +414,7 @@ impl PollTable {
             polls.push(record);
         }
 
-        Ok(PollTable {
-            polls,
-            jurisdiction,
-        })
+        Ok(PollTable::new(polls, jurisdiction))
     }
 
     /// Creates a [PollTable] based on an input &str, which must be formatted exactly as the Europe Elects .csv format.
@@ -259,7 +429,24 @@ impl PollTable {
     /// let example_poll = PollTable::from_str(example, "de").unwrap();
     /// ```
     pub fn from_str(s: &str, jurisdiction: &str) -> Result<PollTable, PollTableFromStrError> {
-        let mut rdr = ReaderBuilder::new().from_reader(s.as_bytes());
+        PollTable::from_reader(s.as_bytes(), jurisdiction)
+    }
+
+    /// As with [PollTable::from_str], but reads the .csv data from any [std::io::Read], such as a network response or an in-memory buffer,
+    /// without touching the filesystem.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,30%,70%";
+    /// let poll_table = PollTable::from_reader(std::io::Cursor::new(example), "de").unwrap();
+    ///
+    /// assert_eq!(poll_table.polls().len(), 1);
+    /// ```
+    pub fn from_reader<R: std::io::Read>(
+        reader: R,
+        jurisdiction: &str,
+    ) -> Result<PollTable, PollTableFromStrError> {
+        let mut rdr = ReaderBuilder::new().from_reader(reader);
         let mut polls: Vec<Poll> = Vec::new();
 
         // Jurisdiction
@@ -274,10 +461,7 @@ impl PollTable {
             polls.push(record);
         }
 
-        Ok(PollTable {
-            polls,
-            jurisdiction: final_jurisdiction,
-        })
+        Ok(PollTable::new(polls, final_jurisdiction))
     }
 
     /// Returns all opinion polls as a Vec of [Poll]s, indexed from newest to oldest.
@@ -385,6 +569,34 @@ impl PollTable {
         let diff = *last_date - *first_date;
         diff.num_days() as usize
     }
+
+    /// Returns the index of the previous poll by the same polling firm with the same scope as the given poll,
+    /// which is the one whose fieldwork ended most recently before the given poll's fieldwork ended.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,60%,30%,10%
+    /// Other Polling,The Daily Snail,2024-03-01,2024-03-02,National,2054,Provided,Not Available,1%,55%,35%,10%
+    /// Epic Polling,The Daily Snail,2024-02-20,2024-02-22,European,2054,Provided,Not Available,1%,50%,40%,10%
+    /// Epic Polling,The Daily Snail,2024-02-06,2024-02-08,National,2054,Provided,Not Available,1%,58%,32%,10%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    ///
+    /// assert_eq!(poll_table.previous_poll(0), Some(3));
+    /// assert_eq!(poll_table.previous_poll(3), None);
+    /// ```
+    pub fn previous_poll(&self, index: usize) -> Option<usize> {
+        let poll = self.polls.get(index)?;
+        self.polls
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| {
+                other.polling_firm == poll.polling_firm
+                    && other.scope == poll.scope
+                    && other.fieldwork_end < poll.fieldwork_end
+            })
+            .max_by(|(i, a), (j, b)| a.fieldwork_end.cmp(&b.fieldwork_end).then(j.cmp(i)))
+            .map(|(i, _)| i)
+    }
 }
 
 impl RawPollTable {
@@ -392,6 +604,11 @@ impl RawPollTable {
     pub fn new(polls: Vec<Poll>) -> Self {
         RawPollTable { polls }
     }
+
+    /// Returns the polls in the table.
+    pub fn polls(&self) -> &Vec<Poll> {
+        &self.polls
+    }
 }
 
 impl Poll {
@@ -426,6 +643,19 @@ impl Poll {
     pub fn party_results(&self) -> &HashMap<String, PollOption<PercentageOrSeats>> {
         &self.party_results
     }
+
+    /// Returns the middle day of the poll's fieldwork, rounded down.
+    pub fn fieldwork_midpoint(&self) -> NaiveDate {
+        self.fieldwork_start + (self.fieldwork_end - self.fieldwork_start) / 2
+    }
+
+    /// Returns the sample size, or [DEFAULT_SAMPLE_SIZE] if it is not available.
+    pub(crate) fn effective_sample_size(&self) -> f32 {
+        match self.sample_size {
+            PollOption::Some(size) if size > 0.0 => size,
+            _ => DEFAULT_SAMPLE_SIZE,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -479,7 +709,7 @@ impl<T> PollOption<T> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Scope {
     National,
     European,
@@ -505,6 +735,55 @@ impl PercentageOrSeats {
         }
     }
 }
+
+impl fmt::Display for Percentage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+impl fmt::Display for Seats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for PercentageOrSeats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PercentageOrSeats::Percentage(val) => val.fmt(f),
+            PercentageOrSeats::Seats(val) => val.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::National => write!(f, "National"),
+            Scope::European => write!(f, "European"),
+        }
+    }
+}
+
+impl fmt::Display for SampleSizeQualification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleSizeQualification::Provided => write!(f, "Provided"),
+            SampleSizeQualification::EstimatedAssumed => write!(f, "Estimated/Assumed"),
+        }
+    }
+}
+
+/// Displays values as they are written in the Europe Elects .csv format, with "Not Available" for missing values.
+impl<T: fmt::Display> fmt::Display for PollOption<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PollOption::Some(val) => val.fmt(f),
+            PollOption::NotAvailable => write!(f, "Not Available"),
+        }
+    }
+}
 impl<'de, 'a: 'de> Deserialize<'de> for PollOption<String> {
     fn deserialize<D>(deserializer: D) -> Result<PollOption<String>, D::Error>
     where
@@ -589,12 +868,34 @@ impl<'de> Deserialize<'de> for PollOption<Percentage> {
     }
 }
 
+/// Party results are read as percentages such as "30%", seat counts such as "120", or "Not Available".
+/// ```
+/// use europe_elects_csv::*;
+/// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+/// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,Not Available,120,80,Not Available";
+/// let poll_table = PollTable::from_str(example, "de").unwrap();
+///
+/// let result = &poll_table.party_results(0)["First Party"];
+/// assert!(matches!(result, PollOption::Some(PercentageOrSeats::Seats(_))));
+/// assert_eq!(result.poll_unwrap().value(), 120.0);
+/// ```
 impl<'de> Deserialize<'de> for PollOption<PercentageOrSeats> {
     fn deserialize<D>(deserializer: D) -> Result<PollOption<PercentageOrSeats>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let val: String = Deserialize::deserialize(deserializer)?;
+        // Party columns are flattened, so the csv crate may already have parsed seat counts as numbers.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f32),
+            Text(String),
+        }
+
+        let val = match Raw::deserialize(deserializer)? {
+            Raw::Number(val) => return Ok(PollOption::Some(PercentageOrSeats::Seats(Seats(val)))),
+            Raw::Text(val) => val,
+        };
 
         match val.as_str() {
             "Not Available" | "N/A" => Ok(PollOption::NotAvailable),
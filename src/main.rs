@@ -0,0 +1,448 @@
+//! Command line interface to the europe-elects-csv library.
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use europe_elects_csv::alerts::AlertRules;
+#[cfg(feature = "chart")]
+use europe_elects_csv::chart::Chart;
+use europe_elects_csv::coalitions::{
+    sort_by_connectedness, sort_by_seats, Coalition, CoalitionCalculator, Majority,
+};
+use europe_elects_csv::collection::PollTableCollection;
+#[cfg(feature = "chart")]
+use europe_elects_csv::elections::ElectionHistory;
+use europe_elects_csv::identity::PollId;
+use europe_elects_csv::party::PartyMetadata;
+use europe_elects_csv::seats::{AllocationMethod, ElectoralSystem, Quota};
+#[cfg(feature = "xlsx")]
+use europe_elects_csv::spreadsheet::Spreadsheet;
+use europe_elects_csv::watch::{AtomFeed, WatchEventKind, Watcher};
+use europe_elects_csv::PollTable;
+use std::error::Error;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists the alerts raised by a set of rules for a Europe Elects .csv file or a directory of them.
+    Alerts(AlertsArgs),
+    /// Projects seats from a poll and lists possible coalitions.
+    Coalitions(CoalitionsArgs),
+    /// Compares two versions of the same Europe Elects .csv file.
+    Diff(DiffArgs),
+    /// Draws a chart of the polls in a Europe Elects .csv file as SVG or PNG.
+    #[cfg(feature = "chart")]
+    Chart(ChartArgs),
+    /// Browses a Europe Elects .csv file, or a directory of them, in an interactive terminal interface.
+    #[cfg(feature = "tui")]
+    Tui(TuiArgs),
+    /// Watches a directory of Europe Elects .csv files and prints new, corrected and removed polls as JSON lines.
+    Watch(WatchArgs),
+    /// Writes a Europe Elects .csv file, or a directory of them, to an Excel workbook.
+    #[cfg(feature = "xlsx")]
+    Xlsx(XlsxArgs),
+}
+
+#[derive(Args)]
+struct SeatArgs {
+    /// Total number of seats in the parliament.
+    #[arg(long)]
+    seats: u32,
+    /// Seat allocation method.
+    #[arg(long, value_enum, default_value_t = Method::Dhondt)]
+    method: Method,
+    /// Legal threshold, as a percentage of the total vote.
+    #[arg(long, default_value_t = 0.0)]
+    threshold: f32,
+}
+
+impl SeatArgs {
+    fn system(&self) -> ElectoralSystem {
+        let method = match self.method {
+            Method::Dhondt => AllocationMethod::DHondt,
+            Method::SainteLague => AllocationMethod::SainteLague,
+            Method::Hare => AllocationMethod::LargestRemainder(Quota::Hare),
+            Method::Droop => AllocationMethod::LargestRemainder(Quota::Droop),
+        };
+        ElectoralSystem::new(self.seats, method, self.threshold)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Method {
+    Dhondt,
+    SainteLague,
+    Hare,
+    Droop,
+}
+
+#[cfg(any(feature = "chart", feature = "xlsx"))]
+#[derive(Clone, Copy, ValueEnum)]
+enum PollScope {
+    National,
+    European,
+}
+
+#[cfg(any(feature = "chart", feature = "xlsx"))]
+impl From<PollScope> for europe_elects_csv::Scope {
+    fn from(scope: PollScope) -> Self {
+        match scope {
+            PollScope::National => europe_elects_csv::Scope::National,
+            PollScope::European => europe_elects_csv::Scope::European,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Ranking {
+    Seats,
+    Connectedness,
+}
+
+#[derive(Args)]
+struct CoalitionsArgs {
+    /// Europe Elects .csv file to read.
+    path: String,
+    /// Index of the poll to project, where 0 is the newest.
+    #[arg(long, default_value_t = 0)]
+    poll: usize,
+    #[command(flatten)]
+    seat_args: SeatArgs,
+    /// Majority needed: "simple", "constitutional" or a number of seats.
+    #[arg(long, default_value = "simple", value_parser = parse_majority)]
+    majority: Majority,
+    /// Party metadata .csv file with left-right positions.
+    #[arg(long)]
+    parties: Option<String>,
+    /// Order in which minimal winning coalitions are listed.
+    #[arg(long, value_enum, default_value_t = Ranking::Seats)]
+    rank: Ranking,
+    /// Comma-separated coalition to check instead of listing minimal winning coalitions. May be repeated.
+    #[arg(long)]
+    check: Vec<String>,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// Old version of the file.
+    old: String,
+    /// New version of the file.
+    new: String,
+    /// Print the changeset as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[cfg(feature = "chart")]
+#[derive(Args)]
+struct ChartArgs {
+    /// Europe Elects .csv file to read.
+    path: String,
+    /// Output file, ending in .svg or .png.
+    #[arg(long, short)]
+    output: String,
+    /// Party metadata .csv file with party colors.
+    #[arg(long)]
+    parties: Option<String>,
+    /// Election results .csv file, whose elections of the chart's scope are marked on the chart.
+    #[arg(long)]
+    elections: Option<String>,
+    /// Scope of the polls averaged and of the elections marked.
+    #[arg(long, value_enum, default_value_t = PollScope::National)]
+    scope: PollScope,
+    /// Threshold to draw as a horizontal line, in percent. May be repeated.
+    #[arg(long)]
+    threshold: Vec<f32>,
+    /// Days before and after each date included in the average line.
+    #[arg(long, default_value_t = 28)]
+    window: i64,
+    /// Width of the chart in pixels.
+    #[arg(long, default_value_t = 1200)]
+    width: u32,
+    /// Height of the chart in pixels.
+    #[arg(long, default_value_t = 600)]
+    height: u32,
+    /// Chart title, instead of the jurisdiction's name.
+    #[arg(long)]
+    title: Option<String>,
+}
+
+#[cfg(feature = "xlsx")]
+#[derive(Args)]
+struct XlsxArgs {
+    /// Europe Elects .csv file or directory of them to read.
+    path: String,
+    /// Output .xlsx file.
+    #[arg(long, short)]
+    output: String,
+    /// Party metadata .csv file with party colors.
+    #[arg(long)]
+    parties: Option<String>,
+    /// Add a sheet with the latest average of every jurisdiction.
+    #[arg(long)]
+    summary: bool,
+    /// Days of polls included in the summary averages.
+    #[arg(long, default_value_t = 28)]
+    window: i64,
+    /// Scope of the polls included in the summary averages.
+    #[arg(long, value_enum, default_value_t = PollScope::National)]
+    scope: PollScope,
+}
+
+#[cfg(feature = "tui")]
+#[derive(Args)]
+struct TuiArgs {
+    /// Europe Elects .csv file or directory to open.
+    path: String,
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    /// Directory to watch.
+    dir: String,
+    /// Seconds between checks.
+    #[arg(long, default_value_t = 60)]
+    interval: u64,
+    /// Atom feed file to write after every change.
+    #[arg(long)]
+    feed: Option<String>,
+    /// Number of entries kept in the feed.
+    #[arg(long, default_value_t = 100)]
+    feed_entries: usize,
+    /// Alert rules .csv file, whose alerts raised by new or corrected polls are also printed.
+    #[arg(long)]
+    rules: Option<String>,
+}
+
+#[derive(Args)]
+struct AlertsArgs {
+    /// Europe Elects .csv file or directory to check.
+    path: String,
+    /// Alert rules .csv file.
+    #[arg(long)]
+    rules: String,
+    /// Only list alerts raised by polls whose fieldwork ended on or after this date (YYYY-MM-DD).
+    #[arg(long)]
+    since: Option<NaiveDate>,
+    /// Print alerts as JSON lines.
+    #[arg(long)]
+    json: bool,
+}
+
+fn parse_majority(s: &str) -> Result<Majority, String> {
+    match s {
+        "simple" => Ok(Majority::Simple),
+        "constitutional" => Ok(Majority::Constitutional),
+        _ => s
+            .parse()
+            .map(Majority::Custom)
+            .map_err(|_| format!("invalid majority: {s}")),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Alerts(args) => alerts(args),
+        Command::Coalitions(args) => coalitions(args),
+        Command::Diff(args) => diff(args),
+        #[cfg(feature = "chart")]
+        Command::Chart(args) => chart(args),
+        #[cfg(feature = "tui")]
+        Command::Tui(args) => tui(args),
+        Command::Watch(args) => watch(args),
+        #[cfg(feature = "xlsx")]
+        Command::Xlsx(args) => xlsx(args),
+    }
+}
+
+fn coalitions(args: CoalitionsArgs) -> Result<(), Box<dyn Error>> {
+    let poll_table = PollTable::try_from_path(&args.path)?;
+    let poll = poll_table
+        .poll_by_index(args.poll)
+        .ok_or("poll index out of range")?;
+    let seats = args.seat_args.system().project(poll);
+    let metadata = args
+        .parties
+        .as_deref()
+        .map(PartyMetadata::try_from_path)
+        .transpose()?;
+
+    let mut calculator = CoalitionCalculator::new(&seats, args.majority);
+    if let Some(metadata) = &metadata {
+        calculator = calculator.with_metadata(metadata);
+    }
+
+    let mut coalitions: Vec<Coalition> = if args.check.is_empty() {
+        calculator.minimal_winning()?
+    } else {
+        args.check
+            .iter()
+            .map(|check| calculator.evaluate(&check.split(',').map(str::trim).collect::<Vec<_>>()))
+            .collect()
+    };
+    match args.rank {
+        Ranking::Seats => sort_by_seats(&mut coalitions),
+        Ranking::Connectedness => sort_by_connectedness(&mut coalitions),
+    }
+
+    println!("Majority: {} seats", calculator.seats_needed());
+    for coalition in coalitions {
+        let connected = match coalition.is_connected() {
+            Some(true) => ", connected",
+            Some(false) => ", not connected",
+            None => "",
+        };
+        println!(
+            "{}: {} seats ({} {}{})",
+            coalition.members().join(" + "),
+            coalition.seats(),
+            if coalition.is_majority() {
+                "surplus"
+            } else {
+                "short by"
+            },
+            coalition.surplus().abs(),
+            connected,
+        );
+    }
+    Ok(())
+}
+
+fn diff(args: DiffArgs) -> Result<(), Box<dyn Error>> {
+    let old = PollTable::try_from_path(&args.old)?;
+    let new = PollTable::try_from_path(&args.new)?;
+    let diff = PollTable::diff(&old, &new);
+    if args.json {
+        println!("{}", diff.to_json());
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "chart")]
+fn chart(args: ChartArgs) -> Result<(), Box<dyn Error>> {
+    let poll_table = PollTable::try_from_path(&args.path)?;
+    let metadata = args
+        .parties
+        .as_deref()
+        .map(PartyMetadata::try_from_path)
+        .transpose()?;
+    let history = args
+        .elections
+        .as_deref()
+        .map(ElectionHistory::try_from_path)
+        .transpose()?;
+
+    let mut chart = Chart::new(&poll_table)
+        .size(args.width, args.height)
+        .window_days(args.window)
+        .scope(args.scope.into());
+    if let Some(metadata) = &metadata {
+        chart = chart.with_metadata(metadata);
+    }
+    if let Some(history) = &history {
+        chart = chart.elections(history, &args.scope.into());
+    }
+    if let Some(title) = &args.title {
+        chart = chart.title(title);
+    }
+    for threshold in args.threshold {
+        chart = chart.threshold(threshold);
+    }
+    chart.save(&args.output)?;
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn tui(args: TuiArgs) -> Result<(), Box<dyn Error>> {
+    let collection = if std::path::Path::new(&args.path).is_dir() {
+        PollTableCollection::try_from_dir(&args.path)?
+    } else {
+        PollTableCollection::new(vec![PollTable::try_from_path(&args.path)?])
+    };
+    europe_elects_csv::tui::run(&collection)?;
+    Ok(())
+}
+
+fn watch(args: WatchArgs) -> Result<(), Box<dyn Error>> {
+    let watcher = Watcher::new(&args.dir)?;
+    let rules = args
+        .rules
+        .as_deref()
+        .map(AlertRules::try_from_path)
+        .transpose()?;
+    let mut feed = AtomFeed::new(&format!("Polls in {}", args.dir)).max_entries(args.feed_entries);
+    watcher.run(Duration::from_secs(args.interval), |watcher, events| {
+        for event in events {
+            println!("{}", event.to_json_line());
+        }
+        if let Some(rules) = &rules {
+            // Only report alerts raised by polls that are new or were corrected in this check.
+            let changed: Vec<PollId> = events
+                .iter()
+                .filter(|event| event.kind != WatchEventKind::Removed)
+                .map(|event| event.poll.id)
+                .collect();
+            for table in watcher.tables() {
+                for alert in rules.evaluate(table, None) {
+                    if changed.iter().any(|id| alert.involves(id)) {
+                        println!("{}", alert.to_json_line());
+                    }
+                }
+            }
+        }
+        if let Some(path) = &args.feed {
+            feed.push(events);
+            feed.save(path)?;
+        }
+        Ok(true)
+    })?;
+    Ok(())
+}
+
+fn alerts(args: AlertsArgs) -> Result<(), Box<dyn Error>> {
+    let rules = AlertRules::try_from_path(&args.rules)?;
+    let collection = if std::path::Path::new(&args.path).is_dir() {
+        PollTableCollection::try_from_dir(&args.path)?
+    } else {
+        PollTableCollection::new(vec![PollTable::try_from_path(&args.path)?])
+    };
+    for alert in rules.evaluate_collection(&collection, args.since) {
+        if args.json {
+            println!("{}", alert.to_json_line());
+        } else {
+            println!("{alert}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "xlsx")]
+fn xlsx(args: XlsxArgs) -> Result<(), Box<dyn Error>> {
+    let metadata = args
+        .parties
+        .as_deref()
+        .map(PartyMetadata::try_from_path)
+        .transpose()?;
+    let collection = if std::path::Path::new(&args.path).is_dir() {
+        PollTableCollection::try_from_dir(&args.path)?
+    } else {
+        PollTableCollection::new(vec![PollTable::try_from_path(&args.path)?])
+    };
+    let mut spreadsheet = Spreadsheet::from_collection(&collection);
+    if let Some(metadata) = &metadata {
+        spreadsheet = spreadsheet.with_metadata(metadata);
+    }
+    spreadsheet
+        .summary(args.summary)
+        .window_days(args.window)
+        .scope(args.scope.into())
+        .save(&args.output)?;
+    Ok(())
+}
@@ -0,0 +1,250 @@
+//! Detection of polls whose results deviate from the polling consensus by more than their sampling error explains.
+use crate::average::AverageOptions;
+use crate::{PercentageOrSeats, PollOption, PollTable, Scope};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone)]
+/// Settings for [PollTable::outlier_scores] and [PollTable::outliers].
+pub struct OutlierOptions {
+    window_days: i64,
+    threshold: f32,
+    house_effects: bool,
+}
+
+impl Default for OutlierOptions {
+    fn default() -> Self {
+        OutlierOptions::new()
+    }
+}
+
+impl OutlierOptions {
+    /// Creates options comparing polls to other polls within 14 days, flagging z-scores of 3 or more and correcting for house effects.
+    pub fn new() -> Self {
+        OutlierOptions {
+            window_days: 14,
+            threshold: 3.0,
+            house_effects: true,
+        }
+    }
+
+    /// Sets how many days before and after a poll's fieldwork midpoint other polls form its consensus.
+    pub fn window_days(mut self, window_days: i64) -> Self {
+        self.window_days = window_days;
+        self
+    }
+
+    /// Sets the absolute z-score from which a party result makes its poll an outlier.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets whether the consensus is shifted by the polling firm's house effect before comparing.
+    pub fn house_effects(mut self, house_effects: bool) -> Self {
+        self.house_effects = house_effects;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+/// How a party's result in a poll compares to the consensus.
+pub struct PartyDeviation {
+    result: f32,
+    expected: f32,
+    house_effect: f32,
+    z_score: f32,
+}
+
+impl PartyDeviation {
+    /// Returns the party's result in the poll, in percent.
+    pub fn result(&self) -> f32 {
+        self.result
+    }
+
+    /// Returns the expected result: the average of the other polls, plus the house effect.
+    pub fn expected(&self) -> f32 {
+        self.expected
+    }
+
+    /// Returns the firm's house effect for the party, in percentage points.
+    pub fn house_effect(&self) -> f32 {
+        self.house_effect
+    }
+
+    /// Returns the deviation from the expected result, in standard errors of the poll.
+    pub fn z_score(&self) -> f32 {
+        self.z_score
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The deviations of a poll from the consensus, as returned by [PollTable::outlier_scores].
+pub struct PollOutlier {
+    index: usize,
+    parties: BTreeMap<String, PartyDeviation>,
+    score: f32,
+}
+
+impl PollOutlier {
+    /// Returns the index of the poll in its [PollTable].
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the deviation of every party which could be compared, in alphabetical order.
+    pub fn parties(&self) -> &BTreeMap<String, PartyDeviation> {
+        &self.parties
+    }
+
+    /// Returns the z-score of a party.
+    pub fn z_score(&self, party: &str) -> Option<f32> {
+        self.parties.get(party).map(PartyDeviation::z_score)
+    }
+
+    /// Returns the overall outlier score: the root mean square of the party z-scores.
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+
+    /// Returns the largest absolute z-score of any party.
+    pub fn max_abs_z(&self) -> f32 {
+        self.parties
+            .values()
+            .map(|deviation| deviation.z_score.abs())
+            .fold(0.0, f32::max)
+    }
+}
+
+impl PollTable {
+    /// Compares every poll with the centered average of the other polls of its scope around its fieldwork midpoint.
+    ///
+    /// A firm's house effect for a party is the mean deviation of the firm's other polls of the same scope from their own consensus.
+    /// Standard errors assume simple random sampling, with [crate::DEFAULT_SAMPLE_SIZE] for polls without a sample size.
+    /// Polls with no comparable party results are left out.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::outliers::OutlierOptions;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,Not Available,2024-03-01,2024-03-03,National,1000,Provided,Not Available,1%,20%,30%,50%
+    /// Other Polling,Not Available,2024-03-02,2024-03-04,National,1000,Provided,Not Available,1%,21%,30%,49%
+    /// Odd Polling,Not Available,2024-03-03,2024-03-05,National,1000,Provided,Not Available,1%,30%,30%,40%
+    /// Last Polling,Not Available,2024-03-04,2024-03-06,National,1000,Provided,Not Available,1%,20%,31%,49%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let outliers = poll_table.outliers(&OutlierOptions::new());
+    ///
+    /// assert_eq!(outliers.len(), 1);
+    /// assert_eq!(outliers[0].index(), 2);
+    /// assert!(outliers[0].z_score("First Party").unwrap() > 5.0);
+    /// ```
+    pub fn outlier_scores(&self, options: &OutlierOptions) -> Vec<PollOutlier> {
+        let average_options = AverageOptions::new()
+            .window_days(options.window_days)
+            .centered(true);
+
+        // Deviation of each poll's results from the average of all other polls.
+        let deviations: Vec<BTreeMap<&String, (f32, f32)>> = self
+            .polls
+            .iter()
+            .enumerate()
+            .map(|(i, poll)| {
+                let average_options = average_options.clone().scope(poll.scope);
+                let average =
+                    self.average_excluding(&poll.fieldwork_midpoint(), &average_options, Some(i));
+                poll.party_results
+                    .iter()
+                    .filter_map(|(party, result)| match result {
+                        PollOption::Some(PercentageOrSeats::Percentage(share)) => {
+                            let consensus = average.value(party)?;
+                            Some((party, (share.value(), consensus)))
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut by_firm: HashMap<(String, Scope), Vec<usize>> = HashMap::new();
+        for (i, poll) in self.polls.iter().enumerate() {
+            by_firm
+                .entry((poll.polling_firm.to_lowercase(), poll.scope))
+                .or_default()
+                .push(i);
+        }
+
+        let mut outliers = Vec::new();
+        for (i, poll) in self.polls.iter().enumerate() {
+            let sample_size = poll.effective_sample_size();
+            let firm_polls = &by_firm[&(poll.polling_firm.to_lowercase(), poll.scope)];
+            let parties: BTreeMap<String, PartyDeviation> = deviations[i]
+                .iter()
+                .map(|(party, (result, consensus))| {
+                    let house_effect = if options.house_effects {
+                        house_effect(&deviations, firm_polls, i, party)
+                    } else {
+                        0.0
+                    };
+                    let expected = consensus + house_effect;
+                    let p = expected.clamp(0.5, 99.5) / 100.0;
+                    let standard_error = (p * (1.0 - p) / sample_size).sqrt() * 100.0;
+                    let deviation = PartyDeviation {
+                        result: *result,
+                        expected,
+                        house_effect,
+                        z_score: (result - expected) / standard_error,
+                    };
+                    (party.to_string(), deviation)
+                })
+                .collect();
+            if parties.is_empty() {
+                continue;
+            }
+            let score = (parties
+                .values()
+                .map(|deviation| deviation.z_score.powi(2))
+                .sum::<f32>()
+                / parties.len() as f32)
+                .sqrt();
+            outliers.push(PollOutlier {
+                index: i,
+                parties,
+                score,
+            });
+        }
+        outliers
+    }
+
+    /// Returns the polls with at least one party whose absolute z-score reaches the threshold, most extreme first.
+    pub fn outliers(&self, options: &OutlierOptions) -> Vec<PollOutlier> {
+        let mut outliers: Vec<PollOutlier> = self
+            .outlier_scores(options)
+            .into_iter()
+            .filter(|outlier| outlier.max_abs_z() >= options.threshold)
+            .collect();
+        outliers.sort_by(|a, b| b.score.total_cmp(&a.score));
+        outliers
+    }
+}
+
+/// Returns the mean deviation of a party in the firm's polls other than the poll at `index`, or 0 if there are none.
+fn house_effect(
+    deviations: &[BTreeMap<&String, (f32, f32)>],
+    firm_polls: &[usize],
+    index: usize,
+    party: &String,
+) -> f32 {
+    let others: Vec<f32> = firm_polls
+        .iter()
+        .filter(|&&j| j != index)
+        .filter_map(|&j| {
+            deviations[j]
+                .get(party)
+                .map(|(result, consensus)| result - consensus)
+        })
+        .collect();
+    if others.is_empty() {
+        0.0
+    } else {
+        others.iter().sum::<f32>() / others.len() as f32
+    }
+}
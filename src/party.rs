@@ -0,0 +1,155 @@
+//! Metadata about parties, keyed by the party column names used in the Europe Elects .csv files.
+use crate::errors::PartyMetadataError;
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default)]
+/// Information about one party which is not contained in the poll files themselves.
+pub struct PartyInfo {
+    position: Option<f32>,
+    ep_group: Option<String>,
+    color: Option<String>,
+}
+
+impl PartyInfo {
+    /// Creates a [PartyInfo] with no information.
+    pub fn new() -> Self {
+        PartyInfo::default()
+    }
+
+    /// Sets the party's ideological position on a left-right scale, where lower values are further left.
+    pub fn with_position(mut self, position: f32) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Returns the party's ideological position on a left-right scale, if known.
+    pub fn position(&self) -> Option<f32> {
+        self.position
+    }
+
+    /// Sets the abbreviation of the European Parliament group the party belongs to, such as "EPP" or "S&D".
+    pub fn with_ep_group(mut self, ep_group: &str) -> Self {
+        self.ep_group = Some(ep_group.to_string());
+        self
+    }
+
+    /// Returns the abbreviation of the party's European Parliament group, if known.
+    pub fn ep_group(&self) -> Option<&str> {
+        self.ep_group.as_deref()
+    }
+
+    /// Sets the party's color, as a CSS color such as "#e3000f".
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_string());
+        self
+    }
+
+    /// Returns the party's color, if known.
+    pub fn color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A collection of [PartyInfo]s, keyed by party column name.
+pub struct PartyMetadata {
+    parties: HashMap<String, PartyInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PartyRecord {
+    #[serde(rename = "Party")]
+    party: String,
+    #[serde(rename = "Position", default)]
+    position: Option<f32>,
+    #[serde(rename = "EP Group", default)]
+    ep_group: Option<String>,
+    #[serde(rename = "Color", default)]
+    color: Option<String>,
+}
+
+impl PartyMetadata {
+    /// Creates an empty [PartyMetadata].
+    pub fn new() -> Self {
+        PartyMetadata::default()
+    }
+
+    /// Adds or replaces the information for one party.
+    pub fn insert(&mut self, party: &str, info: PartyInfo) {
+        self.parties.insert(party.to_string(), info);
+    }
+
+    /// Returns the information for one party, if any.
+    pub fn get(&self, party: &str) -> Option<&PartyInfo> {
+        self.parties.get(party)
+    }
+
+    /// As with [PartyMetadata::from_str], but reads the .csv data from a file.
+    pub fn try_from_path(path: &str) -> Result<PartyMetadata, PartyMetadataError> {
+        let s = std::fs::read_to_string(path)?;
+        PartyMetadata::from_str(&s)
+    }
+}
+
+impl FromStr for PartyMetadata {
+    type Err = PartyMetadataError;
+
+    /// Reads party metadata from .csv data with a "Party" column containing party column names, and optional "Position", "EP Group" and "Color" columns.
+    /// Empty cells are treated as unknown.
+    /// ```
+    /// use europe_elects_csv::party::PartyMetadata;
+    /// use std::str::FromStr;
+    /// let example = "Party,Position,EP Group
+    /// Left Party,2.5,S&D
+    /// Right Party,8,ECR
+    /// Unknown Party,,";
+    /// let metadata = PartyMetadata::from_str(example).unwrap();
+    ///
+    /// assert_eq!(metadata.get("Right Party").unwrap().position(), Some(8.0));
+    /// assert_eq!(metadata.get("Right Party").unwrap().ep_group(), Some("ECR"));
+    /// assert_eq!(metadata.get("Unknown Party").unwrap().position(), None);
+    /// ```
+    fn from_str(s: &str) -> Result<PartyMetadata, PartyMetadataError> {
+        let mut rdr = ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(s.as_bytes());
+        let mut metadata = PartyMetadata::new();
+
+        for result in rdr.deserialize() {
+            let record: PartyRecord = result?;
+            metadata.insert(
+                &record.party,
+                PartyInfo {
+                    position: record.position,
+                    ep_group: record.ep_group,
+                    color: record.color,
+                },
+            );
+        }
+
+        Ok(metadata)
+    }
+}
+
+/// Parses a "#rgb" or "#rrggbb" color.
+pub(crate) fn parse_color(color: &str) -> Option<u32> {
+    let hex = color.strip_prefix('#')?;
+    match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok(),
+        3 => {
+            let short = u32::from_str_radix(hex, 16).ok()?;
+            let (r, g, b) = ((short >> 8) & 0xf, (short >> 4) & 0xf, short & 0xf);
+            Some(((r * 0x11) << 16) | ((g * 0x11) << 8) | (b * 0x11))
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether white text is more legible than black on a color.
+pub(crate) fn is_dark(rgb: u32) -> bool {
+    let (r, g, b) = ((rgb >> 16) & 0xff, (rgb >> 8) & 0xff, rgb & 0xff);
+    (299 * r + 587 * g + 114 * b) / 1000 < 128
+}
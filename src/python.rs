@@ -0,0 +1,434 @@
+//! Python bindings, built as the `europe_elects_csv` extension module with `maturin build --features python`.
+//!
+//! ```python
+//! import europe_elects_csv as ee
+//!
+//! table = ee.PollTable.from_path("de.csv")
+//! recent = table.filter(scope="National", since=datetime.date(2024, 1, 1))
+//! for poll in recent:
+//!     print(poll.polling_firm, poll.fieldwork_end, poll.results["SPD"])
+//! frame = recent.to_pandas()
+//! ```
+//!
+//! DataFrames are not zero-copy: there is no Arrow export, so every value is copied into one Python list per column
+//! and then again into the DataFrame. Party columns hold percentages only; seat counts are read from [PyPoll::results] and [PyPoll::units].
+use crate::average::AverageOptions;
+use crate::collection::PollTableCollection;
+use crate::outliers::OutlierOptions;
+use crate::query::PollQuery;
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable, Scope};
+use chrono::NaiveDate;
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::collections::BTreeMap;
+
+fn value_error(error: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn result_value(result: &PollOption<PercentageOrSeats>) -> Option<f32> {
+    match result {
+        PollOption::Some(result) => Some(result.value()),
+        PollOption::NotAvailable => None,
+    }
+}
+
+fn parse_scope(scope: &str) -> PyResult<Scope> {
+    match scope.to_lowercase().as_str() {
+        "national" => Ok(Scope::National),
+        "european" => Ok(Scope::European),
+        _ => Err(value_error(format!("invalid scope: {scope}"))),
+    }
+}
+
+fn percentage(result: &PollOption<PercentageOrSeats>) -> Option<f32> {
+    match result {
+        PollOption::Some(PercentageOrSeats::Percentage(share)) => Some(share.value()),
+        _ => None,
+    }
+}
+
+#[pyclass(name = "Poll", module = "europe_elects_csv", frozen)]
+/// One poll of a [PyPollTable].
+pub struct PyPoll {
+    index: usize,
+    poll: Poll,
+}
+
+#[pymethods]
+impl PyPoll {
+    /// The stable id of the poll, as 16 hexadecimal digits.
+    #[getter]
+    fn id(&self) -> String {
+        self.poll.id().to_string()
+    }
+
+    /// The index of the poll in its table, where 0 is the newest.
+    #[getter]
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    #[getter]
+    fn polling_firm(&self) -> &str {
+        &self.poll.polling_firm
+    }
+
+    #[getter]
+    fn commissioners(&self) -> Option<&str> {
+        match &self.poll.commissioners {
+            PollOption::Some(commissioners) => Some(commissioners),
+            PollOption::NotAvailable => None,
+        }
+    }
+
+    #[getter]
+    fn fieldwork_start(&self) -> NaiveDate {
+        self.poll.fieldwork_start
+    }
+
+    #[getter]
+    fn fieldwork_end(&self) -> NaiveDate {
+        self.poll.fieldwork_end
+    }
+
+    #[getter]
+    fn scope(&self) -> String {
+        self.poll.scope.to_string()
+    }
+
+    #[getter]
+    fn sample_size(&self) -> Option<f32> {
+        match self.poll.sample_size {
+            PollOption::Some(size) => Some(size),
+            PollOption::NotAvailable => None,
+        }
+    }
+
+    /// The result of every party, in percent or seats, or None where not available.
+    #[getter]
+    fn results(&self) -> BTreeMap<String, Option<f32>> {
+        self.poll
+            .party_results
+            .iter()
+            .map(|(party, result)| (party.clone(), result_value(result)))
+            .collect()
+    }
+
+    /// Whether each available party result is in "percent" or "seats".
+    #[getter]
+    fn units(&self) -> BTreeMap<String, &'static str> {
+        self.poll
+            .party_results
+            .iter()
+            .filter_map(|(party, result)| match result {
+                PollOption::Some(PercentageOrSeats::Percentage(_)) => {
+                    Some((party.clone(), "percent"))
+                }
+                PollOption::Some(PercentageOrSeats::Seats(_)) => Some((party.clone(), "seats")),
+                PollOption::NotAvailable => None,
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn other(&self) -> Option<f32> {
+        result_value(&self.poll.other)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Poll {} by {}, {} to {}>",
+            self.poll.id(),
+            self.poll.polling_firm,
+            self.poll.fieldwork_start,
+            self.poll.fieldwork_end
+        )
+    }
+}
+
+#[pyclass(name = "PollTable", module = "europe_elects_csv", frozen)]
+/// A [PollTable] of one jurisdiction.
+pub struct PyPollTable {
+    table: PollTable,
+}
+
+impl PyPollTable {
+    fn py_poll(&self, index: usize) -> PyPoll {
+        PyPoll {
+            index,
+            poll: self.table.polls[index].clone(),
+        }
+    }
+
+    fn parties(&self) -> Vec<String> {
+        let mut parties: Vec<String> = self
+            .table
+            .polls
+            .iter()
+            .flat_map(|poll| poll.party_results.keys().cloned())
+            .collect();
+        parties.sort();
+        parties.dedup();
+        parties
+    }
+}
+
+#[pymethods]
+impl PyPollTable {
+    /// Reads a Europe Elects .csv file, whose name gives the jurisdiction.
+    #[staticmethod]
+    fn from_path(path: &str) -> PyResult<Self> {
+        let table = PollTable::try_from_path(path).map_err(value_error)?;
+        Ok(PyPollTable { table })
+    }
+
+    /// Reads Europe Elects .csv data for a jurisdiction code, such as "de".
+    #[staticmethod]
+    fn from_str(data: &str, jurisdiction: &str) -> PyResult<Self> {
+        let table = PollTable::from_str(data, jurisdiction).map_err(value_error)?;
+        Ok(PyPollTable { table })
+    }
+
+    /// The jurisdiction code, such as "de".
+    #[getter]
+    fn jurisdiction(&self) -> &'static str {
+        self.table.jurisdiction.code()
+    }
+
+    /// The jurisdiction's name.
+    #[getter]
+    fn jurisdiction_name(&self) -> &'static str {
+        self.table.jurisdiction.name()
+    }
+
+    /// The party columns, in alphabetical order.
+    #[pyo3(name = "parties")]
+    fn py_parties(&self) -> Vec<String> {
+        self.parties()
+    }
+
+    fn __len__(&self) -> usize {
+        self.table.polls.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<PyPoll> {
+        let len = self.table.polls.len() as isize;
+        let index = if index < 0 { index + len } else { index };
+        if !(0..len).contains(&index) {
+            return Err(PyIndexError::new_err("poll index out of range"));
+        }
+        Ok(self.py_poll(index as usize))
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let polls: Vec<PyPoll> = (0..self.table.polls.len())
+            .map(|i| self.py_poll(i))
+            .collect();
+        Ok(PyList::new(py, polls)?.try_iter()?.into_any())
+    }
+
+    /// Returns a table with only the polls matching every given condition, as in [PollQuery].
+    #[pyo3(signature = (firm=None, scope=None, since=None, until=None))]
+    fn filter(
+        &self,
+        firm: Option<&str>,
+        scope: Option<&str>,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> PyResult<Self> {
+        let mut query = PollQuery::new();
+        if let Some(firm) = firm {
+            query = query.firm(firm);
+        }
+        if let Some(scope) = scope {
+            query = query.scope(parse_scope(scope)?);
+        }
+        if let Some(since) = since {
+            query = query.from(since);
+        }
+        if let Some(until) = until {
+            query = query.until(until);
+        }
+        Ok(PyPollTable {
+            table: self.table.filtered(&query),
+        })
+    }
+
+    /// Returns the weighted average share of every party in polls of a scope at a date, by default the end of the latest fieldwork of that scope.
+    #[pyo3(signature = (date=None, window_days=28, centered=false, scope="National"))]
+    fn average(
+        &self,
+        date: Option<NaiveDate>,
+        window_days: i64,
+        centered: bool,
+        scope: &str,
+    ) -> PyResult<BTreeMap<String, f32>> {
+        let scope = parse_scope(scope)?;
+        let date = date
+            .or_else(|| {
+                self.table
+                    .polls
+                    .iter()
+                    .filter(|poll| poll.scope == scope)
+                    .map(|poll| poll.fieldwork_end)
+                    .max()
+            })
+            .ok_or_else(|| value_error("the table has no polls"))?;
+        let options = AverageOptions::new()
+            .window_days(window_days)
+            .centered(centered)
+            .scope(scope);
+        Ok(self
+            .table
+            .average_at(&date, &options)
+            .parties()
+            .iter()
+            .map(|(party, average)| (party.clone(), average.value()))
+            .collect())
+    }
+
+    /// Returns the index and score of every outlier poll, most extreme first.
+    #[pyo3(signature = (window_days=14, threshold=3.0))]
+    fn outliers(&self, window_days: i64, threshold: f32) -> Vec<(usize, f32)> {
+        let options = OutlierOptions::new()
+            .window_days(window_days)
+            .threshold(threshold);
+        self.table
+            .outliers(&options)
+            .iter()
+            .map(|outlier| (outlier.index(), outlier.score()))
+            .collect()
+    }
+
+    /// Returns the changes from this table to a newer version of it, as JSON.
+    fn diff(&self, new: &PyPollTable) -> String {
+        PollTable::diff(&self.table, &new.table).to_json()
+    }
+
+    /// Returns the table as a dict of equally long column lists, with None where a value is not available.
+    /// Party columns are in percent, so results given as seats are None as well; [PyPoll::units] tells them apart.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let polls = &self.table.polls;
+        let dict = PyDict::new(py);
+        dict.set_item(
+            "id",
+            polls
+                .iter()
+                .map(|poll| poll.id().to_string())
+                .collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "polling_firm",
+            polls
+                .iter()
+                .map(|poll| &poll.polling_firm)
+                .collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "commissioners",
+            polls
+                .iter()
+                .map(|poll| match &poll.commissioners {
+                    PollOption::Some(commissioners) => Some(commissioners),
+                    PollOption::NotAvailable => None,
+                })
+                .collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "fieldwork_start",
+            polls
+                .iter()
+                .map(|poll| poll.fieldwork_start)
+                .collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "fieldwork_end",
+            polls
+                .iter()
+                .map(|poll| poll.fieldwork_end)
+                .collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "scope",
+            polls
+                .iter()
+                .map(|poll| poll.scope.to_string())
+                .collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "sample_size",
+            polls
+                .iter()
+                .map(|poll| match poll.sample_size {
+                    PollOption::Some(size) => Some(size),
+                    PollOption::NotAvailable => None,
+                })
+                .collect::<Vec<_>>(),
+        )?;
+        for party in self.parties() {
+            let column: Vec<Option<f32>> = polls
+                .iter()
+                .map(|poll| poll.party_results.get(&party).and_then(percentage))
+                .collect();
+            dict.set_item(party, column)?;
+        }
+        dict.set_item(
+            "Other",
+            polls
+                .iter()
+                .map(|poll| percentage(&poll.other))
+                .collect::<Vec<_>>(),
+        )?;
+        Ok(dict)
+    }
+
+    /// Returns the table as a pandas DataFrame, copied from [PyPollTable::to_dict]. Requires pandas to be installed.
+    fn to_pandas<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        py.import("pandas")?
+            .getattr("DataFrame")?
+            .call1((self.to_dict(py)?,))
+    }
+
+    /// Returns the table as a polars DataFrame, copied from [PyPollTable::to_dict]. Requires polars to be installed.
+    fn to_polars<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        py.import("polars")?
+            .getattr("DataFrame")?
+            .call1((self.to_dict(py)?,))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<PollTable {} with {} polls>",
+            self.table.jurisdiction.code(),
+            self.table.polls.len()
+        )
+    }
+}
+
+/// Loads every Europe Elects .csv file in a directory, keyed by jurisdiction code.
+#[pyfunction]
+fn load_dir(path: &str) -> PyResult<BTreeMap<&'static str, PyPollTable>> {
+    let collection = PollTableCollection::try_from_dir(path).map_err(value_error)?;
+    Ok(collection
+        .tables()
+        .iter()
+        .map(|table| {
+            (
+                table.jurisdiction.code(),
+                PyPollTable {
+                    table: table.clone(),
+                },
+            )
+        })
+        .collect())
+}
+
+#[pymodule]
+fn europe_elects_csv(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyPoll>()?;
+    module.add_class::<PyPollTable>()?;
+    module.add_function(wrap_pyfunction!(load_dir, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Selection of polls by polling firm, scope and date.
+use crate::{Poll, PollTable, Scope};
+use chrono::NaiveDate;
+
+#[derive(Debug, Clone, Default)]
+/// Conditions a poll must meet to be selected by [PollTable::query]. An empty query selects every poll.
+pub struct PollQuery {
+    firm: Option<String>,
+    scope: Option<Scope>,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+impl PollQuery {
+    /// Creates a query selecting every poll.
+    pub fn new() -> Self {
+        PollQuery::default()
+    }
+
+    /// Only selects polls whose polling firm contains the text, ignoring case.
+    pub fn firm(mut self, firm: &str) -> Self {
+        self.firm = Some(firm.to_lowercase());
+        self
+    }
+
+    /// Only selects polls of a scope.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Only selects polls whose fieldwork ended on or after a date.
+    pub fn from(mut self, from: NaiveDate) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Only selects polls whose fieldwork ended on or before a date.
+    pub fn until(mut self, until: NaiveDate) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Returns whether a poll meets every condition of the query.
+    pub fn matches(&self, poll: &Poll) -> bool {
+        self.firm
+            .as_ref()
+            .is_none_or(|firm| poll.polling_firm.to_lowercase().contains(firm))
+            && self.scope.is_none_or(|scope| poll.scope == scope)
+            && self.from.is_none_or(|from| poll.fieldwork_end >= from)
+            && self.until.is_none_or(|until| poll.fieldwork_end <= until)
+    }
+}
+
+impl PollTable {
+    /// Returns the indices of the polls selected by a query.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::query::PollQuery;
+    /// use chrono::NaiveDate;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,70%
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,European,1000,Provided,Not Available,1%,28%,72%
+    /// Other Polling,Not Available,2024-01-06,2024-01-08,National,1000,Provided,Not Available,1%,28%,72%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let query = PollQuery::new()
+    ///     .firm("epic")
+    ///     .scope(Scope::National)
+    ///     .from(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+    ///
+    /// assert_eq!(poll_table.query(&query), vec![0]);
+    /// assert_eq!(poll_table.filtered(&PollQuery::new().scope(Scope::National)).polls().len(), 2);
+    /// ```
+    pub fn query(&self, query: &PollQuery) -> Vec<usize> {
+        self.polls
+            .iter()
+            .enumerate()
+            .filter(|(_, poll)| query.matches(poll))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns a copy of the table with only the polls selected by a query.
+    pub fn filtered(&self, query: &PollQuery) -> PollTable {
+        PollTable::new(
+            self.polls
+                .iter()
+                .filter(|poll| query.matches(poll))
+                .cloned()
+                .collect(),
+            self.jurisdiction,
+        )
+    }
+}
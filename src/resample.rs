@@ -0,0 +1,318 @@
+//! Aggregation of polls into regular weekly, monthly or quarterly periods.
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable, Scope};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+/// The length of the periods polls are grouped into.
+pub enum Period {
+    /// ISO weeks, starting on Monday.
+    Week,
+    /// Calendar months.
+    Month,
+    /// Calendar quarters.
+    Quarter,
+}
+
+impl Period {
+    /// Returns the first day of the period containing a date.
+    pub fn start_of(&self, date: &NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week => *date - Days::new(date.weekday().num_days_from_monday() as u64),
+            Period::Month => date.with_day(1).expect("every month has a first day"),
+            Period::Quarter => {
+                let month = date.month0() / 3 * 3 + 1;
+                NaiveDate::from_ymd_opt(date.year(), month, 1)
+                    .expect("every quarter has a first day")
+            }
+        }
+    }
+
+    /// Returns the first day of the period after the one starting at `start`.
+    fn next(&self, start: &NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week => *start + Days::new(7),
+            Period::Month => *start + Months::new(1),
+            Period::Quarter => *start + Months::new(3),
+        }
+    }
+
+    /// Returns the name of the period starting at `start`, such as "2024-W10", "2024-03" or "2024-Q1".
+    fn label(&self, start: &NaiveDate) -> String {
+        match self {
+            Period::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month => start.format("%Y-%m").to_string(),
+            Period::Quarter => format!("{}-Q{}", start.year(), start.month0() / 3 + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Which date of a poll decides its period.
+pub enum DateAssignment {
+    /// The middle day of fieldwork.
+    #[default]
+    FieldworkMidpoint,
+    /// The last day of fieldwork.
+    FieldworkEnd,
+}
+
+impl DateAssignment {
+    fn date(&self, poll: &Poll) -> NaiveDate {
+        match self {
+            DateAssignment::FieldworkMidpoint => poll.fieldwork_midpoint(),
+            DateAssignment::FieldworkEnd => poll.fieldwork_end,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Settings for [PollTable::resample].
+pub struct ResampleOptions {
+    period: Period,
+    assignment: DateAssignment,
+    scope: Scope,
+}
+
+impl ResampleOptions {
+    /// Creates options for grouping national polls into periods by their fieldwork midpoint.
+    pub fn new(period: Period) -> Self {
+        ResampleOptions {
+            period,
+            assignment: DateAssignment::default(),
+            scope: Scope::National,
+        }
+    }
+
+    /// Sets which date of a poll decides its period.
+    pub fn assignment(mut self, assignment: DateAssignment) -> Self {
+        self.assignment = assignment;
+        self
+    }
+
+    /// Sets the scope of the polls grouped, as national and European Parliament polls ask different questions.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+/// Summary statistics of a party's results in a period, in percent.
+pub struct PartyAggregate {
+    mean: f32,
+    median: f32,
+    min: f32,
+    max: f32,
+    polls: usize,
+    sample_size: f32,
+}
+
+impl PartyAggregate {
+    fn new(mut values: Vec<f32>, sample_size: f32) -> Self {
+        values.sort_by(f32::total_cmp);
+        let n = values.len();
+        let median = if n % 2 == 1 {
+            values[n / 2]
+        } else {
+            (values[n / 2 - 1] + values[n / 2]) / 2.0
+        };
+        PartyAggregate {
+            mean: values.iter().sum::<f32>() / n as f32,
+            median,
+            min: values[0],
+            max: values[n - 1],
+            polls: n,
+            sample_size,
+        }
+    }
+
+    /// Returns the unweighted mean result.
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+
+    /// Returns the median result.
+    pub fn median(&self) -> f32 {
+        self.median
+    }
+
+    /// Returns the lowest result.
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    /// Returns the highest result.
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// Returns the number of polls with a result for the party.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    /// Returns the total sample size of the polls with a result for the party, counting only provided sample sizes.
+    pub fn sample_size(&self) -> f32 {
+        self.sample_size
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The polls of one period.
+pub struct Bucket {
+    label: String,
+    start: NaiveDate,
+    end: NaiveDate,
+    polls: usize,
+    parties: BTreeMap<String, PartyAggregate>,
+}
+
+impl Bucket {
+    /// Returns the name of the period, such as "2024-W10", "2024-03" or "2024-Q1".
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the first day of the period.
+    pub fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Returns the last day of the period.
+    pub fn end(&self) -> NaiveDate {
+        self.end
+    }
+
+    /// Returns the number of polls in the period.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    /// Returns the statistics of every party with a result in the period, in alphabetical order.
+    pub fn parties(&self) -> &BTreeMap<String, PartyAggregate> {
+        &self.parties
+    }
+
+    /// Returns the statistics of a party.
+    pub fn party(&self, party: &str) -> Option<&PartyAggregate> {
+        self.parties.get(party)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A regular time series of [Bucket]s, as returned by [PollTable::resample].
+///
+/// Every period from the first to the last poll has a bucket, including periods without polls.
+pub struct ResampledPolls {
+    period: Period,
+    buckets: Vec<Bucket>,
+}
+
+impl ResampledPolls {
+    /// Returns the length of the periods.
+    pub fn period(&self) -> Period {
+        self.period
+    }
+
+    /// Returns the buckets in chronological order.
+    pub fn buckets(&self) -> &[Bucket] {
+        &self.buckets
+    }
+
+    /// Returns every party with a result in any period, in alphabetical order.
+    pub fn parties(&self) -> Vec<&str> {
+        let parties: BTreeSet<&str> = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| bucket.parties.keys().map(String::as_str))
+            .collect();
+        parties.into_iter().collect()
+    }
+
+    /// Returns the mean result of a party in every period, or [None] for periods without a result for the party.
+    pub fn series(&self, party: &str) -> Vec<(NaiveDate, Option<f32>)> {
+        self.buckets
+            .iter()
+            .map(|bucket| (bucket.start, bucket.party(party).map(PartyAggregate::mean)))
+            .collect()
+    }
+}
+
+impl PollTable {
+    /// Groups the polls of a scope into periods and summarises each party's results per period.
+    /// Only results given as percentages are included.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::resample::{Period, ResampleOptions};
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2024-01-02,2024-01-04,National,1000,Provided,Not Available,1%,20%,80%
+    /// Other Polling,Not Available,2024-01-20,2024-01-22,National,1500,Provided,Not Available,1%,24%,76%
+    /// Epic Polling,Not Available,2024-03-29,2024-04-02,National,1000,Provided,Not Available,1%,22%,78%
+    /// Epic Polling,Not Available,2024-01-10,2024-01-12,European,1000,Provided,Not Available,1%,40%,60%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let monthly = poll_table.resample(&ResampleOptions::new(Period::Month));
+    /// let january = &monthly.buckets()[0];
+    ///
+    /// assert_eq!(monthly.buckets().len(), 3);
+    /// assert_eq!(january.label(), "2024-01");
+    /// assert_eq!(january.party("First Party").unwrap().mean(), 22.0);
+    /// assert_eq!(january.party("First Party").unwrap().sample_size(), 2500.0);
+    /// assert_eq!(monthly.buckets()[1].polls(), 0);
+    /// assert_eq!(monthly.series("First Party")[2].1, Some(22.0));
+    /// ```
+    pub fn resample(&self, options: &ResampleOptions) -> ResampledPolls {
+        let period = options.period;
+        let mut grouped: BTreeMap<NaiveDate, Vec<&Poll>> = BTreeMap::new();
+        for poll in self.polls.iter().filter(|poll| poll.scope == options.scope) {
+            grouped
+                .entry(period.start_of(&options.assignment.date(poll)))
+                .or_default()
+                .push(poll);
+        }
+
+        let mut buckets = Vec::new();
+        let (Some(first), Some(last)) = (grouped.keys().next(), grouped.keys().next_back()) else {
+            return ResampledPolls { period, buckets };
+        };
+        let mut start = *first;
+        while start <= *last {
+            let next = period.next(&start);
+            let polls = grouped.get(&start).map(Vec::as_slice).unwrap_or_default();
+            buckets.push(Bucket {
+                label: period.label(&start),
+                start,
+                end: next.pred_opt().expect("periods end after the minimum date"),
+                polls: polls.len(),
+                parties: aggregate(polls),
+            });
+            start = next;
+        }
+        ResampledPolls { period, buckets }
+    }
+}
+
+fn aggregate(polls: &[&Poll]) -> BTreeMap<String, PartyAggregate> {
+    let mut results: BTreeMap<&String, (Vec<f32>, f32)> = BTreeMap::new();
+    for poll in polls {
+        for (party, result) in &poll.party_results {
+            if let PollOption::Some(PercentageOrSeats::Percentage(share)) = result {
+                let (values, sample_size) = results.entry(party).or_default();
+                values.push(share.value());
+                if let PollOption::Some(size) = poll.sample_size {
+                    *sample_size += size;
+                }
+            }
+        }
+    }
+    results
+        .into_iter()
+        .map(|(party, (values, sample_size))| {
+            (party.clone(), PartyAggregate::new(values, sample_size))
+        })
+        .collect()
+}
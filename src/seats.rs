@@ -0,0 +1,227 @@
+//! Seat allocation methods used to turn vote shares into parliamentary seats.
+use crate::{PercentageOrSeats, Poll, PollOption};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+/// Methods by which seats are distributed between parties.
+pub enum AllocationMethod {
+    /// Highest averages with divisors 1, 2, 3, ...
+    DHondt,
+    /// Highest averages with divisors 1, 3, 5, ...
+    SainteLague,
+    /// Sainte-Laguë with a custom first divisor, e.g. 1.2 in Sweden or 1.4 in Norway.
+    ModifiedSainteLague(f32),
+    /// Largest remainder method using the given quota.
+    LargestRemainder(Quota),
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Quotas used by [AllocationMethod::LargestRemainder].
+pub enum Quota {
+    /// Votes divided by seats.
+    Hare,
+    /// Votes divided by seats plus one.
+    Droop,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The number of seats, the allocation method and the legal threshold of one parliament.
+pub struct ElectoralSystem {
+    seats: u32,
+    method: AllocationMethod,
+    threshold: f32,
+}
+
+impl ElectoralSystem {
+    /// Creates a new [ElectoralSystem]. The threshold is a percentage of the total vote, e.g. 5.0 for 5%.
+    pub fn new(seats: u32, method: AllocationMethod, threshold: f32) -> Self {
+        ElectoralSystem {
+            seats,
+            method,
+            threshold,
+        }
+    }
+
+    /// Returns the total number of seats in the parliament.
+    pub fn seats(&self) -> u32 {
+        self.seats
+    }
+
+    /// Returns the allocation method of the parliament.
+    pub fn method(&self) -> AllocationMethod {
+        self.method
+    }
+
+    /// Returns the legal threshold as a percentage of the total vote.
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Allocates seats to parties based on their share of the vote, given as percentages of the total vote.
+    /// Parties below the threshold receive no seats, and every party with a share is present in the output.
+    /// ```
+    /// use europe_elects_csv::seats::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let system = ElectoralSystem::new(10, AllocationMethod::DHondt, 5.0);
+    /// let shares = HashMap::from([
+    ///     (String::from("A"), 50.0),
+    ///     (String::from("B"), 30.0),
+    ///     (String::from("C"), 16.0),
+    ///     (String::from("D"), 4.0),
+    /// ]);
+    /// let seats = system.allocate(&shares);
+    ///
+    /// assert_eq!(seats["A"], 6);
+    /// assert_eq!(seats["B"], 3);
+    /// assert_eq!(seats["C"], 1);
+    /// assert_eq!(seats["D"], 0);
+    /// ```
+    pub fn allocate(&self, shares: &HashMap<String, f32>) -> HashMap<String, u32> {
+        let eligible: HashMap<String, f32> = shares
+            .iter()
+            .filter(|(_, share)| **share >= self.threshold)
+            .map(|(party, share)| (party.clone(), *share))
+            .collect();
+
+        let mut seats = allocate(&eligible, self.seats, self.method);
+        for party in shares.keys() {
+            seats.entry(party.clone()).or_insert(0);
+        }
+        seats
+    }
+
+    /// Projects the seats of each party from one poll.
+    /// Results given as percentages are allocated with [ElectoralSystem::allocate], while polls that already give seats are used as they are.
+    /// Parties which are not available are left out.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::seats::*;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Third Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,45%,40%,Not Available,15%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let system = ElectoralSystem::new(17, AllocationMethod::SainteLague, 5.0);
+    /// let seats = system.project(poll_table.poll_by_index(0).unwrap());
+    ///
+    /// assert_eq!(seats["First Party"], 9);
+    /// assert_eq!(seats["Second Party"], 8);
+    /// assert!(!seats.contains_key("Third Party"));
+    /// ```
+    pub fn project(&self, poll: &Poll) -> HashMap<String, u32> {
+        let mut shares: HashMap<String, f32> = HashMap::new();
+        let mut seats: HashMap<String, u32> = HashMap::new();
+        for (party, result) in &poll.party_results {
+            match result {
+                PollOption::Some(PercentageOrSeats::Percentage(share)) => {
+                    shares.insert(party.clone(), share.value());
+                }
+                PollOption::Some(PercentageOrSeats::Seats(s)) => {
+                    seats.insert(party.clone(), s.value().round() as u32);
+                }
+                PollOption::NotAvailable => {}
+            }
+        }
+        seats.extend(self.allocate(&shares));
+        seats
+    }
+}
+
+/// Distributes a number of seats between parties proportionally to their votes, without applying a threshold.
+/// Ties are broken in favour of the party with more votes, then alphabetically.
+pub fn allocate(
+    votes: &HashMap<String, f32>,
+    seats: u32,
+    method: AllocationMethod,
+) -> HashMap<String, u32> {
+    let mut parties: Vec<(&String, f64)> = votes
+        .iter()
+        .filter(|(_, votes)| **votes > 0.0)
+        .map(|(party, votes)| (party, *votes as f64))
+        .collect();
+    parties.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let mut allocation: Vec<u32> = vec![0; parties.len()];
+    if parties.is_empty() {
+        return HashMap::new();
+    }
+
+    match method {
+        AllocationMethod::DHondt => {
+            highest_averages(&parties, &mut allocation, seats, |s| s as f64 + 1.0)
+        }
+        AllocationMethod::SainteLague => {
+            highest_averages(&parties, &mut allocation, seats, |s| 2.0 * s as f64 + 1.0)
+        }
+        AllocationMethod::ModifiedSainteLague(first) => {
+            highest_averages(&parties, &mut allocation, seats, |s| match s {
+                0 => first as f64,
+                _ => 2.0 * s as f64 + 1.0,
+            })
+        }
+        AllocationMethod::LargestRemainder(quota) => {
+            largest_remainder(&parties, &mut allocation, seats, quota)
+        }
+    }
+
+    parties
+        .into_iter()
+        .zip(allocation)
+        .map(|((party, _), seats)| (party.clone(), seats))
+        .collect()
+}
+
+fn highest_averages(
+    parties: &[(&String, f64)],
+    allocation: &mut [u32],
+    seats: u32,
+    divisor: impl Fn(u32) -> f64,
+) {
+    for _ in 0..seats {
+        // Parties are sorted by votes, so keeping the first maximum breaks ties in their favour.
+        let mut best = 0;
+        let mut best_quotient = f64::MIN;
+        for (i, (_, votes)) in parties.iter().enumerate() {
+            let quotient = votes / divisor(allocation[i]);
+            if quotient > best_quotient {
+                best = i;
+                best_quotient = quotient;
+            }
+        }
+        allocation[best] += 1;
+    }
+}
+
+fn largest_remainder(parties: &[(&String, f64)], allocation: &mut [u32], seats: u32, quota: Quota) {
+    if seats == 0 {
+        return;
+    }
+    let total: f64 = parties.iter().map(|(_, votes)| votes).sum();
+    let quota = match quota {
+        Quota::Hare => total / seats as f64,
+        Quota::Droop => total / (seats as f64 + 1.0),
+    };
+
+    let mut remainders: Vec<(usize, f64)> = Vec::with_capacity(parties.len());
+    let mut allocated = 0;
+    for (i, (_, votes)) in parties.iter().enumerate() {
+        let full = (votes / quota).floor();
+        allocation[i] = full as u32;
+        allocated += allocation[i];
+        remainders.push((i, votes / quota - full));
+    }
+
+    // A Droop quota can hand out one seat too many on full quotas alone.
+    while allocated > seats {
+        let last = (0..allocation.len())
+            .rev()
+            .find(|i| allocation[*i] > 0)
+            .expect("Allocated seats should belong to a party");
+        allocation[last] -= 1;
+        allocated -= 1;
+    }
+
+    remainders.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (i, _) in remainders.iter().cycle().take((seats - allocated) as usize) {
+        allocation[*i] += 1;
+    }
+}
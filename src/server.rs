@@ -0,0 +1,398 @@
+//! A local HTTP API serving a directory of Europe Elects .csv files as JSON.
+//!
+//! | Route | Query parameters |
+//! | --- | --- |
+//! | `GET /jurisdictions` | |
+//! | `GET /jurisdictions/{code}/polls` | `from`, `until`, `firm`, `scope` |
+//! | `GET /jurisdictions/{code}/average` | as for polls, and `date`, `window`, `centered` |
+//! | `GET /jurisdictions/{code}/seats` | as for average, and `seats`, `method`, `threshold` |
+//!
+//! Dates are written as YYYY-MM-DD, scopes as "national" or "european", and methods as "dhondt", "sainte-lague", "hare" or "droop".
+//! Every response has an ETag, and requests with a matching If-None-Match header get an empty 304 response.
+//! Files are reloaded whenever a .csv file in the directory is added, removed or modified.
+use crate::average::AverageOptions;
+use crate::collection::PollTableCollection;
+use crate::errors::ServerError;
+use crate::identity::Fnv;
+use crate::query::PollQuery;
+use crate::seats::{AllocationMethod, ElectoralSystem, Quota};
+use crate::{init_jurisdiction, PercentageOrSeats, Poll, PollOption, PollTable, Scope};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A response from [PollServer::handle].
+pub struct Response {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The JSON body, which is empty for 304 responses.
+    pub body: String,
+    /// The ETag of the body.
+    pub etag: String,
+}
+
+impl Response {
+    fn json(status: u16, value: Value) -> Self {
+        let body = value.to_string();
+        let mut hasher = Fnv::new();
+        hasher.field(&body);
+        Response {
+            status,
+            body,
+            etag: format!("\"{:016x}\"", hasher.0),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response::json(status, json!({ "error": message }))
+    }
+}
+
+/// The name, size and modification time of every .csv file in a directory, used to notice changes.
+type Signature = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+/// Serves the [PollTableCollection] of a directory.
+pub struct PollServer {
+    dir: String,
+    collection: PollTableCollection,
+    signature: Signature,
+}
+
+impl PollServer {
+    /// Loads the .csv files in a directory.
+    pub fn new(dir: &str) -> Result<PollServer, ServerError> {
+        Ok(PollServer {
+            dir: dir.to_string(),
+            collection: PollTableCollection::try_from_dir(dir)?,
+            signature: signature(dir),
+        })
+    }
+
+    /// Listens on an address such as "127.0.0.1:8080" and answers requests until the process ends.
+    pub fn serve(mut self, address: &str) -> Result<(), ServerError> {
+        let server = tiny_http::Server::http(address).map_err(ServerError::BindError)?;
+        for request in server.incoming_requests() {
+            let if_none_match = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("If-None-Match"))
+                .map(|header| header.value.to_string());
+            let method = request.method().to_string();
+            let response = self.handle(&method, request.url(), if_none_match.as_deref());
+
+            let headers = [
+                ("Content-Type", "application/json"),
+                ("ETag", response.etag.as_str()),
+            ]
+            .into_iter()
+            .filter_map(|(field, value)| {
+                tiny_http::Header::from_bytes(field.as_bytes(), value.as_bytes()).ok()
+            });
+            let mut reply =
+                tiny_http::Response::from_string(response.body).with_status_code(response.status);
+            for header in headers {
+                reply.add_header(header);
+            }
+            if let Err(error) = request.respond(reply) {
+                eprintln!("Failed to send response: {error}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers one request, given its method, URL and If-None-Match header.
+    /// ```
+    /// use europe_elects_csv::server::PollServer;
+    /// let dir = std::env::temp_dir().join("europe-elects-csv-server-handle");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("de.csv"), "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,70%
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,European,1000,Provided,Not Available,1%,20%,80%").unwrap();
+    /// let mut server = PollServer::new(dir.to_str().unwrap()).unwrap();
+    ///
+    /// assert_eq!(server.handle("GET", "/jurisdictions/de/polls", None).status, 200);
+    /// assert_eq!(server.handle("GET", "/jurisdictions/de/polls?firm=%a\u{e9}", None).status, 200);
+    /// assert_eq!(server.handle("GET", "/jurisdictions/xx/polls", None).status, 404);
+    /// let european = server.handle("GET", "/jurisdictions/de/average?scope=european", None);
+    /// assert!(european.body.contains("\"value\":20.0"));
+    /// ```
+    pub fn handle(&mut self, method: &str, url: &str, if_none_match: Option<&str>) -> Response {
+        self.reload_if_changed();
+        if method != "GET" {
+            return Response::error(405, "only GET requests are supported");
+        }
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let params = parse_query(query);
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let response = match segments.as_slice() {
+            [] | ["jurisdictions"] => self.jurisdictions(),
+            ["jurisdictions", code, resource] => match self.table(code) {
+                None => Response::error(404, "unknown jurisdiction"),
+                Some(poll_table) => {
+                    let result = match *resource {
+                        "polls" => polls(poll_table, &params),
+                        "average" => average(poll_table, &params),
+                        "seats" => seats(poll_table, &params),
+                        _ => Err(Response::error(404, "not found")),
+                    };
+                    result.unwrap_or_else(|error| error)
+                }
+            },
+            _ => Response::error(404, "not found"),
+        };
+
+        if response.status == 200 && if_none_match == Some(response.etag.as_str()) {
+            return Response {
+                status: 304,
+                body: String::new(),
+                etag: response.etag,
+            };
+        }
+        response
+    }
+
+    fn reload_if_changed(&mut self) {
+        let current = signature(&self.dir);
+        if current == self.signature {
+            return;
+        }
+        match PollTableCollection::try_from_dir(&self.dir) {
+            Ok(collection) => self.collection = collection,
+            Err(error) => eprintln!("Failed to reload {}: {error}", self.dir),
+        }
+        self.signature = current;
+    }
+
+    fn table(&self, code: &str) -> Option<&PollTable> {
+        let jurisdiction = init_jurisdiction().get(code).copied()?;
+        self.collection.get(jurisdiction)
+    }
+
+    fn jurisdictions(&self) -> Response {
+        let jurisdictions: Vec<Value> = self
+            .collection
+            .tables()
+            .iter()
+            .map(|table| {
+                json!({
+                    "code": table.jurisdiction.code(),
+                    "name": table.jurisdiction.name(),
+                    "polls": table.polls.len(),
+                    "latest_fieldwork_end": table.polls.iter().map(|poll| poll.fieldwork_end).max(),
+                })
+            })
+            .collect();
+        Response::json(200, json!(jurisdictions))
+    }
+}
+
+fn signature(dir: &str) -> Signature {
+    let mut files: Signature = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().extension().and_then(|ext| ext.to_str()) == Some("csv"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.len(), metadata.modified().ok()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Splits a query string into its parameters, decoding "+" and percent-encoded bytes.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let digit = |byte: u8| (byte as char).to_digit(16);
+                match (digit(bytes[i + 1]), digit(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse<T: std::str::FromStr>(
+    params: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>, Response> {
+    params
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| Response::error(400, &format!("invalid {name}")))
+        })
+        .transpose()
+}
+
+fn date(params: &HashMap<String, String>, name: &str) -> Result<Option<NaiveDate>, Response> {
+    params
+        .get(name)
+        .map(|value| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map_err(|_| Response::error(400, &format!("invalid {name}, expected YYYY-MM-DD")))
+        })
+        .transpose()
+}
+
+fn scope(params: &HashMap<String, String>) -> Result<Option<Scope>, Response> {
+    params
+        .get("scope")
+        .map(|scope| match scope.to_lowercase().as_str() {
+            "national" => Ok(Scope::National),
+            "european" => Ok(Scope::European),
+            _ => Err(Response::error(400, "invalid scope")),
+        })
+        .transpose()
+}
+
+fn poll_query(params: &HashMap<String, String>) -> Result<PollQuery, Response> {
+    let mut query = PollQuery::new();
+    if let Some(firm) = params.get("firm") {
+        query = query.firm(firm);
+    }
+    if let Some(scope) = scope(params)? {
+        query = query.scope(scope);
+    }
+    if let Some(from) = date(params, "from")? {
+        query = query.from(from);
+    }
+    if let Some(until) = date(params, "until")? {
+        query = query.until(until);
+    }
+    Ok(query)
+}
+
+fn result_json(result: &PollOption<PercentageOrSeats>) -> Value {
+    match result {
+        PollOption::Some(PercentageOrSeats::Percentage(share)) => {
+            json!({ "value": share.value(), "unit": "percent" })
+        }
+        PollOption::Some(PercentageOrSeats::Seats(seats)) => {
+            json!({ "value": seats.value(), "unit": "seats" })
+        }
+        PollOption::NotAvailable => Value::Null,
+    }
+}
+
+fn poll_json(index: usize, poll: &Poll) -> Value {
+    let results: BTreeMap<&String, Value> = poll
+        .party_results
+        .iter()
+        .map(|(party, result)| (party, result_json(result)))
+        .collect();
+    json!({
+        "index": index,
+        "id": poll.id().to_string(),
+        "polling_firm": poll.polling_firm,
+        "commissioners": match &poll.commissioners {
+            PollOption::Some(commissioners) => Some(commissioners),
+            PollOption::NotAvailable => None,
+        },
+        "fieldwork_start": poll.fieldwork_start,
+        "fieldwork_end": poll.fieldwork_end,
+        "scope": poll.scope.to_string(),
+        "sample_size": match poll.sample_size {
+            PollOption::Some(size) => Some(size),
+            PollOption::NotAvailable => None,
+        },
+        "results": results,
+        "other": result_json(&poll.other),
+    })
+}
+
+fn polls(poll_table: &PollTable, params: &HashMap<String, String>) -> Result<Response, Response> {
+    let query = poll_query(params)?;
+    let polls: Vec<Value> = poll_table
+        .query(&query)
+        .into_iter()
+        .map(|i| poll_json(i, &poll_table.polls[i]))
+        .collect();
+    Ok(Response::json(200, json!(polls)))
+}
+
+/// Computes the average of the selected polls of a scope, national by default, at the given date or the end of the latest fieldwork.
+fn compute_average(
+    poll_table: &PollTable,
+    params: &HashMap<String, String>,
+) -> Result<crate::average::PollAverage, Response> {
+    let filtered = poll_table.filtered(&poll_query(params)?);
+    let scope = scope(params)?.unwrap_or(Scope::National);
+    let date = match date(params, "date")? {
+        Some(date) => date,
+        None => filtered
+            .polls
+            .iter()
+            .filter(|poll| poll.scope == scope)
+            .map(|poll| poll.fieldwork_end)
+            .max()
+            .ok_or_else(|| Response::error(404, "no polls match the query"))?,
+    };
+    let options = AverageOptions::new()
+        .window_days(parse(params, "window")?.unwrap_or(28))
+        .centered(parse(params, "centered")?.unwrap_or(false))
+        .scope(scope);
+    Ok(filtered.average_at(&date, &options))
+}
+
+fn average(poll_table: &PollTable, params: &HashMap<String, String>) -> Result<Response, Response> {
+    let average = compute_average(poll_table, params)?;
+    Ok(Response::json(200, json!(average)))
+}
+
+fn seats(poll_table: &PollTable, params: &HashMap<String, String>) -> Result<Response, Response> {
+    let total: u32 =
+        parse(params, "seats")?.ok_or_else(|| Response::error(400, "missing seats"))?;
+    let method = match params.get("method").map(String::as_str) {
+        None | Some("dhondt") => AllocationMethod::DHondt,
+        Some("sainte-lague") => AllocationMethod::SainteLague,
+        Some("hare") => AllocationMethod::LargestRemainder(Quota::Hare),
+        Some("droop") => AllocationMethod::LargestRemainder(Quota::Droop),
+        Some(_) => return Err(Response::error(400, "invalid method")),
+    };
+    let threshold = parse(params, "threshold")?.unwrap_or(0.0);
+    let average = compute_average(poll_table, params)?;
+    let shares: HashMap<String, f32> = average
+        .parties()
+        .iter()
+        .map(|(party, average)| (party.clone(), average.value()))
+        .collect();
+    let seats: BTreeMap<String, u32> = ElectoralSystem::new(total, method, threshold)
+        .allocate(&shares)
+        .into_iter()
+        .collect();
+    Ok(Response::json(
+        200,
+        json!({ "date": average.date(), "seats": seats }),
+    ))
+}
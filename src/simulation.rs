@@ -0,0 +1,455 @@
+//! Monte Carlo simulation of seat outcomes from vote share estimates with uncertainty.
+use crate::errors::SimulationError;
+use crate::seats::ElectoralSystem;
+use crate::{PercentageOrSeats, Poll, PollOption};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution as _, Gamma, StandardNormal};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+/// The estimated vote share of each party, as a percentage of the total vote, and its standard deviation in percentage points.
+pub struct VoteShareEstimate {
+    parties: Vec<(String, f32, f32)>,
+}
+
+impl VoteShareEstimate {
+    /// Creates an empty [VoteShareEstimate].
+    pub fn new() -> Self {
+        VoteShareEstimate {
+            parties: Vec::new(),
+        }
+    }
+
+    /// Adds or replaces the estimate for one party.
+    pub fn insert(&mut self, party: &str, mean: f32, std_dev: f32) {
+        match self
+            .parties
+            .binary_search_by(|(p, _, _)| p.as_str().cmp(party))
+        {
+            Ok(i) => self.parties[i] = (party.to_string(), mean, std_dev),
+            Err(i) => self.parties.insert(i, (party.to_string(), mean, std_dev)),
+        }
+    }
+
+    /// Builds an estimate from a single poll, using the sampling error implied by its sample size.
+    /// Parties that are not available or given in seats are skipped.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::simulation::VoteShareEstimate;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2500,Provided,Not Available,1%,60%,Not Available,40%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let estimate = VoteShareEstimate::from_poll(poll_table.poll_by_index(0).unwrap());
+    ///
+    /// assert_eq!(estimate.mean("First Party"), Some(60.0));
+    /// assert!((estimate.std_dev("First Party").unwrap() - 0.98).abs() < 0.01);
+    /// assert_eq!(estimate.mean("Second Party"), None);
+    /// ```
+    pub fn from_poll(poll: &Poll) -> Self {
+        let sample_size = poll.effective_sample_size();
+        let mut estimate = VoteShareEstimate::new();
+        for (party, result) in &poll.party_results {
+            if let PollOption::Some(PercentageOrSeats::Percentage(share)) = result {
+                let p = share.value() / 100.0;
+                let std_dev = (p * (1.0 - p) / sample_size).sqrt() * 100.0;
+                estimate.insert(party, share.value(), std_dev);
+            }
+        }
+        estimate
+    }
+
+    /// Returns the estimated mean vote share of a party.
+    pub fn mean(&self, party: &str) -> Option<f32> {
+        self.get(party).map(|(_, mean, _)| *mean)
+    }
+
+    /// Returns the standard deviation of a party's estimated vote share.
+    pub fn std_dev(&self, party: &str) -> Option<f32> {
+        self.get(party).map(|(_, _, std_dev)| *std_dev)
+    }
+
+    /// Returns the parties in the estimate, in alphabetical order.
+    pub fn parties(&self) -> impl Iterator<Item = &str> {
+        self.parties.iter().map(|(party, _, _)| party.as_str())
+    }
+
+    fn get(&self, party: &str) -> Option<&(String, f32, f32)> {
+        self.parties.iter().find(|(p, _, _)| p == party)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The distribution from which vote share scenarios are drawn.
+pub enum Distribution {
+    /// A Dirichlet distribution around the mean shares. Higher concentrations give less spread, and roughly correspond to a sample size.
+    /// Standard deviations in the estimate are ignored, and the concentration must be positive.
+    Dirichlet {
+        /// The sum of the Dirichlet parameters.
+        concentration: f32,
+    },
+    /// A multivariate normal distribution using the estimate's standard deviations, truncated at zero and rescaled to the total of the mean shares (at most 100%).
+    MultivariateNormal(Correlation),
+}
+
+#[derive(Debug, Clone)]
+/// The correlation structure between party errors in [Distribution::MultivariateNormal].
+pub enum Correlation {
+    /// Party errors are independent of each other.
+    Independent,
+    /// Every pair of parties has the same correlation coefficient.
+    Uniform(f32),
+    /// A full correlation matrix, ordered like [VoteShareEstimate::parties].
+    Matrix(Vec<Vec<f32>>),
+}
+
+#[derive(Debug, Clone)]
+/// A seedable Monte Carlo simulation of seat outcomes.
+/// ```
+/// use europe_elects_csv::seats::*;
+/// use europe_elects_csv::simulation::*;
+///
+/// let mut estimate = VoteShareEstimate::new();
+/// estimate.insert("A", 40.0, 2.0);
+/// estimate.insert("B", 35.0, 2.0);
+/// estimate.insert("C", 5.0, 1.0);
+/// let system = ElectoralSystem::new(100, AllocationMethod::DHondt, 5.0);
+///
+/// let result = Simulation::new(estimate, system)
+///     .seed(42)
+///     .coalition("B+C", &["B", "C"])
+///     .run()
+///     .unwrap();
+///
+/// assert!(result.largest_party_probability("A") > 0.9);
+/// assert!(result.threshold_probability("C") > 0.3 && result.threshold_probability("C") < 0.7);
+/// assert!(result.coalition_majority_probability("B+C").unwrap() < 0.5);
+/// ```
+pub struct Simulation {
+    estimate: VoteShareEstimate,
+    system: ElectoralSystem,
+    distribution: Distribution,
+    draws: usize,
+    seed: u64,
+    coalitions: Vec<(String, Vec<String>)>,
+}
+
+impl Simulation {
+    /// Creates a simulation of 10,000 independent multivariate normal draws with a seed of 0.
+    pub fn new(estimate: VoteShareEstimate, system: ElectoralSystem) -> Self {
+        Simulation {
+            estimate,
+            system,
+            distribution: Distribution::MultivariateNormal(Correlation::Independent),
+            draws: 10_000,
+            seed: 0,
+            coalitions: Vec::new(),
+        }
+    }
+
+    /// Sets the distribution from which scenarios are drawn.
+    /// ```
+    /// use europe_elects_csv::seats::*;
+    /// use europe_elects_csv::simulation::*;
+    ///
+    /// let mut estimate = VoteShareEstimate::new();
+    /// estimate.insert("A", 40.0, 2.0);
+    /// let system = ElectoralSystem::new(100, AllocationMethod::DHondt, 5.0);
+    ///
+    /// let simulation = Simulation::new(estimate, system)
+    ///     .distribution(Distribution::Dirichlet { concentration: 0.0 });
+    /// assert!(simulation.run().is_err());
+    /// ```
+    pub fn distribution(mut self, distribution: Distribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// Sets the number of scenarios to draw.
+    pub fn draws(mut self, draws: usize) -> Self {
+        self.draws = draws;
+        self
+    }
+
+    /// Sets the random seed. Simulations with the same inputs and seed give identical results.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Adds a named coalition whose probability of reaching a majority is reported.
+    pub fn coalition(mut self, name: &str, members: &[&str]) -> Self {
+        self.coalitions.push((
+            name.to_string(),
+            members.iter().map(|member| member.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Runs the simulation.
+    pub fn run(&self) -> Result<SimulationResult, SimulationError> {
+        let parties: Vec<String> = self.estimate.parties().map(str::to_string).collect();
+        if parties.is_empty() {
+            return Err(SimulationError::NoPartiesError);
+        }
+        let sampler = Sampler::new(&self.estimate, &self.distribution)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+
+        let mut result = SimulationResult::new(&parties, &self.coalitions, self.system.seats());
+        let mut shares: HashMap<String, f32> = HashMap::with_capacity(parties.len());
+        for _ in 0..self.draws {
+            let draw = sampler.sample(&mut rng);
+            shares.clear();
+            shares.extend(parties.iter().cloned().zip(draw.iter().copied()));
+            let seats = self.system.allocate(&shares);
+            result.record(&shares, &seats, self.system.threshold(), &self.coalitions);
+        }
+        Ok(result)
+    }
+}
+
+enum Sampler {
+    Dirichlet {
+        gammas: Vec<Gamma<f64>>,
+        residual: Option<Gamma<f64>>,
+    },
+    Normal {
+        means: Vec<f64>,
+        total: f64,
+        std_devs: Vec<f64>,
+        cholesky: Vec<Vec<f64>>,
+    },
+}
+
+impl Sampler {
+    fn new(
+        estimate: &VoteShareEstimate,
+        distribution: &Distribution,
+    ) -> Result<Sampler, SimulationError> {
+        let n = estimate.parties.len();
+        match distribution {
+            Distribution::Dirichlet { concentration } => {
+                let concentration = *concentration as f64;
+                if concentration.is_nan() || concentration <= 0.0 {
+                    return Err(SimulationError::InvalidParameterError);
+                }
+                let alpha = |share: f64| (share / 100.0 * concentration).max(f64::EPSILON);
+                let gamma = |share: f64| {
+                    Gamma::new(alpha(share), 1.0)
+                        .map_err(|_| SimulationError::InvalidParameterError)
+                };
+                let gammas = estimate
+                    .parties
+                    .iter()
+                    .map(|(_, mean, _)| gamma(*mean as f64))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let total: f64 = estimate
+                    .parties
+                    .iter()
+                    .map(|(_, mean, _)| *mean as f64)
+                    .sum();
+                // Votes not attributed to any party still take part in the draw.
+                let residual = if total < 100.0 {
+                    Some(gamma(100.0 - total)?)
+                } else {
+                    None
+                };
+                Ok(Sampler::Dirichlet { gammas, residual })
+            }
+            Distribution::MultivariateNormal(correlation) => {
+                let matrix: Vec<Vec<f64>> = match correlation {
+                    Correlation::Independent => identity(n, 0.0),
+                    Correlation::Uniform(rho) => identity(n, *rho as f64),
+                    Correlation::Matrix(matrix) => {
+                        if matrix.len() != n || matrix.iter().any(|row| row.len() != n) {
+                            return Err(SimulationError::CorrelationDimensionError);
+                        }
+                        matrix
+                            .iter()
+                            .map(|row| row.iter().map(|x| *x as f64).collect())
+                            .collect()
+                    }
+                };
+                let means: Vec<f64> = estimate.parties.iter().map(|(_, m, _)| *m as f64).collect();
+                Ok(Sampler::Normal {
+                    total: means.iter().sum::<f64>().min(100.0),
+                    means,
+                    std_devs: estimate.parties.iter().map(|(_, _, s)| *s as f64).collect(),
+                    cholesky: cholesky(&matrix)?,
+                })
+            }
+        }
+    }
+
+    fn sample(&self, rng: &mut ChaCha8Rng) -> Vec<f32> {
+        match self {
+            Sampler::Dirichlet { gammas, residual } => {
+                let draws: Vec<f64> = gammas.iter().map(|gamma| gamma.sample(rng)).collect();
+                let residual = residual.map(|gamma| gamma.sample(rng)).unwrap_or(0.0);
+                let total: f64 = draws.iter().sum::<f64>() + residual;
+                draws
+                    .into_iter()
+                    .map(|x| (x / total * 100.0) as f32)
+                    .collect()
+            }
+            Sampler::Normal {
+                means,
+                total,
+                std_devs,
+                cholesky,
+            } => {
+                let z: Vec<f64> = (0..means.len())
+                    .map(|_| StandardNormal.sample(rng))
+                    .collect();
+                let draws: Vec<f64> = cholesky
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| {
+                        let error: f64 = row.iter().zip(&z).map(|(l, z)| l * z).sum();
+                        (means[i] + std_devs[i] * error).max(0.0)
+                    })
+                    .collect();
+                // Truncating at zero inflates the sum, so draws are scaled back to the total of the means.
+                let sum: f64 = draws.iter().sum();
+                let scale = if sum > 0.0 { total / sum } else { 0.0 };
+                draws.into_iter().map(|x| (x * scale) as f32).collect()
+            }
+        }
+    }
+}
+
+fn identity(n: usize, off_diagonal: f64) -> Vec<Vec<f64>> {
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| if i == j { 1.0 } else { off_diagonal })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the lower triangular Cholesky factor of a symmetric positive definite matrix.
+fn cholesky(matrix: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, SimulationError> {
+    let n = matrix.len();
+    let mut lower = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            if i == j {
+                let diagonal = matrix[i][i] - sum;
+                if diagonal <= 0.0 {
+                    return Err(SimulationError::NotPositiveDefiniteError);
+                }
+                lower[i][j] = diagonal.sqrt();
+            } else {
+                lower[i][j] = (matrix[i][j] - sum) / lower[j][j];
+            }
+        }
+    }
+    Ok(lower)
+}
+
+#[derive(Debug, Clone)]
+/// The outcome of a [Simulation], summarised over all draws.
+pub struct SimulationResult {
+    draws: usize,
+    seats: u32,
+    seat_counts: HashMap<String, Vec<usize>>,
+    above_threshold: HashMap<String, usize>,
+    largest: HashMap<String, f64>,
+    coalition_majorities: HashMap<String, usize>,
+}
+
+impl SimulationResult {
+    fn new(parties: &[String], coalitions: &[(String, Vec<String>)], seats: u32) -> Self {
+        SimulationResult {
+            draws: 0,
+            seats,
+            seat_counts: parties
+                .iter()
+                .map(|party| (party.clone(), vec![0; seats as usize + 1]))
+                .collect(),
+            above_threshold: parties.iter().map(|party| (party.clone(), 0)).collect(),
+            largest: parties.iter().map(|party| (party.clone(), 0.0)).collect(),
+            coalition_majorities: coalitions
+                .iter()
+                .map(|(name, _)| (name.clone(), 0))
+                .collect(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        shares: &HashMap<String, f32>,
+        seats: &HashMap<String, u32>,
+        threshold: f32,
+        coalitions: &[(String, Vec<String>)],
+    ) {
+        self.draws += 1;
+        for (party, share) in shares {
+            let party_seats = seats.get(party).copied().unwrap_or(0);
+            if let Some(counts) = self.seat_counts.get_mut(party) {
+                counts[party_seats as usize] += 1;
+            }
+            if *share >= threshold {
+                *self.above_threshold.entry(party.clone()).or_insert(0) += 1;
+            }
+        }
+
+        // Ties for the largest party split the draw between the tied parties.
+        let most = seats.values().copied().max().unwrap_or(0);
+        if most > 0 {
+            let tied: Vec<&String> = seats
+                .iter()
+                .filter(|(_, s)| **s == most)
+                .map(|(party, _)| party)
+                .collect();
+            for party in &tied {
+                *self.largest.entry((*party).clone()).or_insert(0.0) += 1.0 / tied.len() as f64;
+            }
+        }
+
+        for (name, members) in coalitions {
+            let total: u32 = members.iter().filter_map(|member| seats.get(member)).sum();
+            if total > self.seats / 2 {
+                *self.coalition_majorities.entry(name.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Returns the number of draws in the simulation.
+    pub fn draws(&self) -> usize {
+        self.draws
+    }
+
+    /// Returns how many draws gave the party each number of seats, indexed by the number of seats.
+    pub fn seat_distribution(&self, party: &str) -> Option<&[usize]> {
+        self.seat_counts.get(party).map(Vec::as_slice)
+    }
+
+    /// Returns the mean number of seats won by the party over all draws.
+    pub fn mean_seats(&self, party: &str) -> Option<f64> {
+        let counts = self.seat_counts.get(party)?;
+        let total: usize = counts.iter().enumerate().map(|(seats, n)| seats * n).sum();
+        Some(total as f64 / self.draws.max(1) as f64)
+    }
+
+    /// Returns the probability that the party clears the threshold.
+    pub fn threshold_probability(&self, party: &str) -> f64 {
+        self.probability(self.above_threshold.get(party).copied().unwrap_or(0) as f64)
+    }
+
+    /// Returns the probability that the party wins the most seats.
+    pub fn largest_party_probability(&self, party: &str) -> f64 {
+        self.probability(self.largest.get(party).copied().unwrap_or(0.0))
+    }
+
+    /// Returns the probability that a named coalition wins a majority of seats, or None if no such coalition was simulated.
+    pub fn coalition_majority_probability(&self, name: &str) -> Option<f64> {
+        Some(self.probability(*self.coalition_majorities.get(name)? as f64))
+    }
+
+    fn probability(&self, count: f64) -> f64 {
+        count / self.draws.max(1) as f64
+    }
+}
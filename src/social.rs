@@ -0,0 +1,299 @@
+//! Social media posts summarising a poll in the style of Europe Elects.
+use crate::changes::{PartyChange, PollChange};
+use crate::errors::SocialPostError;
+use crate::party::PartyMetadata;
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable};
+use chrono::{Datelike, NaiveDate};
+
+/// The template used when none is given. See [PostFormatter::template] for the placeholders.
+pub const DEFAULT_TEMPLATE: &str = "{flag} {jurisdiction}, {firm} poll:
+
+{results}
+
+{comparison}
+
+Fieldwork: {fieldwork}
+Sample size: {sample}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The markup of a post.
+pub enum PostFormat {
+    /// Plain text.
+    Plain,
+    /// Markdown, with the heading in bold and the results as a list.
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Social media platforms, which differ in the length of posts they accept.
+pub enum Platform {
+    /// X, formerly Twitter, with a limit of 280 characters.
+    X,
+    /// Bluesky, with a limit of 300 characters.
+    Bluesky,
+    /// Mastodon, with the default limit of 500 characters.
+    Mastodon,
+    /// Threads, with a limit of 500 characters.
+    Threads,
+    /// No character limit.
+    Unlimited,
+}
+
+impl Platform {
+    /// Returns the maximum number of characters in a post, if there is one.
+    pub fn character_limit(&self) -> Option<usize> {
+        match self {
+            Platform::X => Some(280),
+            Platform::Bluesky => Some(300),
+            Platform::Mastodon | Platform::Threads => Some(500),
+            Platform::Unlimited => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Formats polls from a [PollTable] as social media posts.
+/// ```
+/// use europe_elects_csv::*;
+/// use europe_elects_csv::party::*;
+/// use europe_elects_csv::social::*;
+/// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,CDU/CSU,AfD,SPD,Other
+/// INSA,Bild,2024-03-08,2024-03-11,National,1204,Provided,Not Available,0.5%,30%,19%,15%,36%
+/// INSA,Bild,2024-03-01,2024-03-04,National,2004,Provided,Not Available,0.5%,29.5%,20%,15%,35.5%";
+/// let poll_table = PollTable::from_str(example, "de").unwrap();
+/// let mut metadata = PartyMetadata::new();
+/// metadata.insert("CDU/CSU", PartyInfo::new().with_ep_group("EPP"));
+/// metadata.insert("SPD", PartyInfo::new().with_ep_group("S&D"));
+///
+/// let post = PostFormatter::new().with_metadata(&metadata).format(&poll_table, 0).unwrap();
+///
+/// assert_eq!(post, "🇩🇪 Germany, INSA poll:
+///
+/// CDU/CSU-EPP: 30% (+0.5)
+/// AfD: 19% (-1)
+/// SPD-S&D: 15% (-)
+///
+/// +/- vs. 1-4 March 2024
+///
+/// Fieldwork: 8-11 March 2024
+/// Sample size: 1,204");
+/// ```
+pub struct PostFormatter<'a> {
+    metadata: Option<&'a PartyMetadata>,
+    format: PostFormat,
+    platform: Platform,
+    template: String,
+}
+
+impl Default for PostFormatter<'_> {
+    fn default() -> Self {
+        PostFormatter::new()
+    }
+}
+
+impl<'a> PostFormatter<'a> {
+    /// Creates a formatter for plain text posts of unlimited length using [DEFAULT_TEMPLATE].
+    pub fn new() -> Self {
+        PostFormatter {
+            metadata: None,
+            format: PostFormat::Plain,
+            platform: Platform::Unlimited,
+            template: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Uses the EP groups in the party metadata to label parties, as in "SPD-S&D".
+    pub fn with_metadata(mut self, metadata: &'a PartyMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Sets the markup of the post.
+    pub fn format_as(mut self, format: PostFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the platform whose character limit the post must respect.
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    /// Sets the template of the post. The following placeholders are replaced:
+    ///
+    /// - `{flag}`: the flag emoji of the jurisdiction
+    /// - `{jurisdiction}`: the name of the jurisdiction
+    /// - `{firm}`: the polling firm
+    /// - `{commissioners}`: the commissioners, or nothing if not available
+    /// - `{results}`: one line per party, from the highest to the lowest result
+    /// - `{comparison}`: the fieldwork dates of the previous poll the changes refer to
+    /// - `{fieldwork}`: the fieldwork dates
+    /// - `{sample}`: the sample size
+    ///
+    /// Lines whose placeholders all turn out empty, such as `{comparison}` for a firm's first poll, are removed.
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = template.to_string();
+        self
+    }
+
+    /// Formats the poll at the given index, with changes since the same firm's previous poll of the same scope.
+    /// If the post is too long for the platform, the parties with the lowest results are left out until it fits.
+    pub fn format(&self, poll_table: &PollTable, index: usize) -> Result<String, SocialPostError> {
+        let poll = poll_table
+            .poll_by_index(index)
+            .ok_or(SocialPostError::IndexOutOfRangeError)?;
+        let previous = poll_table
+            .previous_poll(index)
+            .and_then(|previous| poll_table.poll_by_index(previous));
+
+        let mut results = self.results(poll, poll_table.changes(index).as_ref());
+        loop {
+            let post = self.render(poll_table, poll, previous, &results);
+            match self.platform.character_limit() {
+                Some(limit) if post.chars().count() > limit => {
+                    if results.pop().is_none() {
+                        return Err(SocialPostError::TooLongError);
+                    }
+                }
+                _ => return Ok(post),
+            }
+        }
+    }
+
+    fn results(&self, poll: &Poll, changes: Option<&PollChange>) -> Vec<String> {
+        let mut parties: Vec<(&String, PercentageOrSeats)> = poll
+            .party_results
+            .iter()
+            .filter_map(|(party, result)| match result {
+                PollOption::Some(result) => Some((party, *result)),
+                PollOption::NotAvailable => None,
+            })
+            .collect();
+        parties.sort_by(|a, b| b.1.value().total_cmp(&a.1.value()).then(a.0.cmp(b.0)));
+
+        parties
+            .into_iter()
+            .map(|(party, result)| {
+                let label = match self.metadata.and_then(|m| m.get(party)?.ep_group()) {
+                    Some(group) => format!("{party}-{group}"),
+                    None => party.clone(),
+                };
+                let value = match result {
+                    PercentageOrSeats::Percentage(p) => format!("{}%", number(p.value())),
+                    PercentageOrSeats::Seats(s) => format!("{} seats", number(s.value())),
+                };
+                let change = changes.map(|changes| {
+                    match changes.party(party).and_then(PartyChange::change) {
+                        Some(change) => match (change * 10.0).round() / 10.0 {
+                            0.0 => String::from(" (-)"),
+                            d if d > 0.0 => format!(" (+{})", number(d)),
+                            d => format!(" ({})", number(d)),
+                        },
+                        None => String::from(" (n.a.)"),
+                    }
+                });
+                let line = format!("{label}: {value}{}", change.unwrap_or_default());
+                match self.format {
+                    PostFormat::Plain => line,
+                    PostFormat::Markdown => format!("- {line}"),
+                }
+            })
+            .collect()
+    }
+
+    fn render(
+        &self,
+        poll_table: &PollTable,
+        poll: &Poll,
+        previous: Option<&Poll>,
+        results: &[String],
+    ) -> String {
+        let commissioners = match &poll.commissioners {
+            PollOption::Some(commissioners) => commissioners.clone(),
+            PollOption::NotAvailable => String::new(),
+        };
+        let comparison = previous
+            .map(|previous| {
+                format!(
+                    "+/- vs. {}",
+                    date_range(&previous.fieldwork_start, &previous.fieldwork_end)
+                )
+            })
+            .unwrap_or_default();
+        let sample = match poll.sample_size {
+            PollOption::Some(size) => thousands(size.round() as u64),
+            PollOption::NotAvailable => String::from("Not Available"),
+        };
+        let jurisdiction = poll_table.jurisdiction();
+        let placeholders = [
+            ("{flag}", jurisdiction.flag()),
+            ("{jurisdiction}", jurisdiction.name().to_string()),
+            ("{firm}", poll.polling_firm.clone()),
+            ("{commissioners}", commissioners),
+            ("{results}", results.join("\n")),
+            ("{comparison}", comparison),
+            (
+                "{fieldwork}",
+                date_range(&poll.fieldwork_start, &poll.fieldwork_end),
+            ),
+            ("{sample}", sample),
+        ];
+
+        let mut lines: Vec<String> = Vec::new();
+        for (i, line) in self.template.lines().enumerate() {
+            let mut rendered = line.to_string();
+            for (placeholder, value) in &placeholders {
+                rendered = rendered.replace(placeholder, value);
+            }
+            if rendered.trim().is_empty() && !line.trim().is_empty() {
+                // Drop the blank line that separated the removed line from the previous one.
+                if lines.last().is_some_and(|last| last.is_empty()) {
+                    lines.pop();
+                }
+                continue;
+            }
+            if i == 0 && self.format == PostFormat::Markdown && !rendered.is_empty() {
+                rendered = format!("**{rendered}**");
+            }
+            lines.push(rendered);
+        }
+        lines.join("\n")
+    }
+}
+
+/// Formats a number with at most one decimal place and no trailing zero.
+fn number(value: f32) -> String {
+    let rounded = (value * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{rounded:.1}")
+    }
+}
+
+fn thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+/// Formats fieldwork dates as "8-11 March 2024", "28 February-3 March 2024" or "28 December 2023-3 January 2024".
+fn date_range(start: &NaiveDate, end: &NaiveDate) -> String {
+    let end_text = end.format("%-d %B %Y").to_string();
+    if start == end {
+        end_text
+    } else if start.year() != end.year() {
+        format!("{}-{end_text}", start.format("%-d %B %Y"))
+    } else if start.month() != end.month() {
+        format!("{}-{end_text}", start.format("%-d %B"))
+    } else {
+        format!("{}-{end_text}", start.day())
+    }
+}
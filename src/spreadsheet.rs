@@ -0,0 +1,364 @@
+//! Excel workbooks of polls, with one sheet per jurisdiction and an optional summary of the latest averages.
+use crate::average::AverageOptions;
+use crate::collection::PollTableCollection;
+use crate::errors::SpreadsheetError;
+use crate::party::{is_dark, parse_color, PartyMetadata};
+use crate::{PercentageOrSeats, PollOption, PollTable, Scope};
+use chrono::{Datelike, NaiveDate};
+use rust_xlsxwriter::{Color, ExcelDateTime, Format, Workbook, Worksheet};
+
+/// Characters Excel does not allow in sheet names.
+const INVALID_SHEET_CHARACTERS: [char; 7] = ['[', ']', ':', '*', '?', '/', '\\'];
+
+/// The metadata columns written before the party columns, as in the Europe Elects .csv format.
+const METADATA_COLUMNS: [&str; 9] = [
+    "Polling Firm",
+    "Commissioners",
+    "Fieldwork Start",
+    "Fieldwork End",
+    "Scope",
+    "Sample Size",
+    "Sample Size Qualification",
+    "Participation",
+    "Precision",
+];
+
+/// Builds an .xlsx workbook from one or more [PollTable]s.
+///
+/// Each table gets a sheet named after its jurisdiction, with dates and percentages as typed cells, a frozen header row, and empty cells where a value is not available.
+/// Party headers are colored from the [PartyMetadata], if given.
+/// ```no_run
+/// use europe_elects_csv::*;
+/// use europe_elects_csv::party::PartyMetadata;
+/// use europe_elects_csv::spreadsheet::Spreadsheet;
+/// let poll_table = PollTable::try_from_path("de.csv").unwrap();
+/// let metadata = PartyMetadata::try_from_path("parties.csv").unwrap();
+///
+/// Spreadsheet::new(&poll_table)
+///     .with_metadata(&metadata)
+///     .summary(true)
+///     .save("de.xlsx")
+///     .unwrap();
+/// ```
+pub struct Spreadsheet<'a> {
+    tables: Vec<&'a PollTable>,
+    metadata: Option<&'a PartyMetadata>,
+    summary: bool,
+    window_days: i64,
+    scope: Scope,
+}
+
+impl<'a> Spreadsheet<'a> {
+    /// Creates a workbook with one sheet for a table.
+    pub fn new(poll_table: &'a PollTable) -> Self {
+        Spreadsheet::from_tables(vec![poll_table])
+    }
+
+    /// Creates a workbook with one sheet per table of a collection, in order of jurisdiction code.
+    pub fn from_collection(collection: &'a PollTableCollection) -> Self {
+        Spreadsheet::from_tables(collection.tables().iter().collect())
+    }
+
+    fn from_tables(tables: Vec<&'a PollTable>) -> Self {
+        Spreadsheet {
+            tables,
+            metadata: None,
+            summary: false,
+            window_days: 28,
+            scope: Scope::National,
+        }
+    }
+
+    /// Colors party headers by the colors in their metadata.
+    pub fn with_metadata(mut self, metadata: &'a PartyMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Adds a first sheet with the average of every party at the end of each table's latest fieldwork of the summary's scope.
+    pub fn summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Sets how many days of polls the summary averages cover, as in [AverageOptions::window_days].
+    pub fn window_days(mut self, window_days: i64) -> Self {
+        self.window_days = window_days;
+        self
+    }
+
+    /// Sets the scope of the polls the summary averages, national by default.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Returns the workbook as the bytes of an .xlsx file.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::spreadsheet::Spreadsheet;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,70%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let bytes = Spreadsheet::new(&poll_table).summary(true).to_bytes().unwrap();
+    ///
+    /// assert!(bytes.starts_with(b"PK"));
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SpreadsheetError> {
+        Ok(self.workbook()?.save_to_buffer()?)
+    }
+
+    /// Writes the workbook to an .xlsx file.
+    pub fn save(&self, path: &str) -> Result<(), SpreadsheetError> {
+        self.workbook()?.save(path)?;
+        Ok(())
+    }
+
+    fn workbook(&self) -> Result<Workbook, SpreadsheetError> {
+        let mut workbook = Workbook::new();
+        let formats = Formats::new();
+        if self.summary {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name("Summary")?;
+            self.write_summary(sheet, &formats)?;
+        }
+        let mut names: Vec<String> = Vec::new();
+        for table in &self.tables {
+            let sheet = workbook.add_worksheet();
+            let name = sheet_name(table, &names);
+            sheet.set_name(&name)?;
+            names.push(name);
+            self.write_table(sheet, table, &formats)?;
+        }
+        Ok(workbook)
+    }
+
+    fn header_format(&self, party: &str, formats: &Formats) -> Format {
+        let color = self
+            .metadata
+            .and_then(|metadata| metadata.get(party))
+            .and_then(|info| info.color())
+            .and_then(parse_color);
+        match color {
+            Some(rgb) => formats
+                .header
+                .clone()
+                .set_background_color(Color::RGB(rgb))
+                .set_font_color(if is_dark(rgb) {
+                    Color::White
+                } else {
+                    Color::Black
+                }),
+            None => formats.header.clone(),
+        }
+    }
+
+    fn write_table(
+        &self,
+        sheet: &mut Worksheet,
+        table: &PollTable,
+        formats: &Formats,
+    ) -> Result<(), SpreadsheetError> {
+        let parties = parties(table);
+        for (col, header) in METADATA_COLUMNS.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *header, &formats.header)?;
+        }
+        let first_party = METADATA_COLUMNS.len() as u16;
+        for (i, party) in parties.iter().enumerate() {
+            let format = self.header_format(party, formats);
+            sheet.write_string_with_format(0, first_party + i as u16, party, &format)?;
+        }
+        let other = first_party + parties.len() as u16;
+        sheet.write_string_with_format(0, other, "Other", &formats.header)?;
+
+        for (i, poll) in table.polls.iter().enumerate() {
+            let row = i as u32 + 1;
+            sheet.write_string(row, 0, &poll.polling_firm)?;
+            if let PollOption::Some(commissioners) = &poll.commissioners {
+                sheet.write_string(row, 1, commissioners)?;
+            }
+            sheet.write_datetime_with_format(
+                row,
+                2,
+                excel_date(poll.fieldwork_start)?,
+                &formats.date,
+            )?;
+            sheet.write_datetime_with_format(
+                row,
+                3,
+                excel_date(poll.fieldwork_end)?,
+                &formats.date,
+            )?;
+            sheet.write_string(row, 4, poll.scope.to_string())?;
+            if let PollOption::Some(size) = poll.sample_size {
+                sheet.write_number_with_format(row, 5, size, &formats.integer)?;
+            }
+            if let PollOption::Some(qualification) = &poll.sample_size_qualification {
+                sheet.write_string(row, 6, qualification.to_string())?;
+            }
+            if let PollOption::Some(participation) = &poll.participation {
+                sheet.write_number_with_format(
+                    row,
+                    7,
+                    fraction(participation.value()),
+                    &formats.percentage,
+                )?;
+            }
+            write_result(sheet, row, 8, &poll.precision, formats)?;
+            for (j, party) in parties.iter().enumerate() {
+                if let Some(result) = poll.party_results.get(party) {
+                    write_result(sheet, row, first_party + j as u16, result, formats)?;
+                }
+            }
+            write_result(sheet, row, other, &poll.other, formats)?;
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+        Ok(())
+    }
+
+    fn write_summary(
+        &self,
+        sheet: &mut Worksheet,
+        formats: &Formats,
+    ) -> Result<(), SpreadsheetError> {
+        let headers = [
+            "Jurisdiction",
+            "Date",
+            "Party",
+            "Average",
+            "Standard Deviation",
+            "Polls",
+        ];
+        for (col, header) in headers.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *header, &formats.header)?;
+        }
+        let options = AverageOptions::new()
+            .window_days(self.window_days)
+            .scope(self.scope);
+        let mut row = 1;
+        for table in &self.tables {
+            let Some(date) = table
+                .polls
+                .iter()
+                .filter(|poll| poll.scope == self.scope)
+                .map(|poll| poll.fieldwork_end)
+                .max()
+            else {
+                continue;
+            };
+            let average = table.average_at(&date, &options);
+            let mut parties: Vec<_> = average.parties().iter().collect();
+            parties.sort_by(|a, b| b.1.value().total_cmp(&a.1.value()));
+            for (party, party_average) in parties {
+                sheet.write_string(row, 0, table.jurisdiction.name())?;
+                sheet.write_datetime_with_format(row, 1, excel_date(date)?, &formats.date)?;
+                sheet.write_string_with_format(
+                    row,
+                    2,
+                    party,
+                    &self.header_format(party, formats),
+                )?;
+                sheet.write_number_with_format(
+                    row,
+                    3,
+                    fraction(party_average.value()),
+                    &formats.percentage,
+                )?;
+                sheet.write_number_with_format(
+                    row,
+                    4,
+                    fraction(party_average.std_dev()),
+                    &formats.percentage,
+                )?;
+                sheet.write_number(row, 5, party_average.polls() as f64)?;
+                row += 1;
+            }
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+        Ok(())
+    }
+}
+
+/// The cell formats shared by every sheet.
+struct Formats {
+    header: Format,
+    date: Format,
+    percentage: Format,
+    integer: Format,
+}
+
+impl Formats {
+    fn new() -> Self {
+        Formats {
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            percentage: Format::new().set_num_format("0.0%"),
+            integer: Format::new().set_num_format("0"),
+        }
+    }
+}
+
+fn write_result(
+    sheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    result: &PollOption<PercentageOrSeats>,
+    formats: &Formats,
+) -> Result<(), SpreadsheetError> {
+    match result {
+        PollOption::Some(PercentageOrSeats::Percentage(share)) => {
+            sheet.write_number_with_format(
+                row,
+                col,
+                fraction(share.value()),
+                &formats.percentage,
+            )?;
+        }
+        PollOption::Some(PercentageOrSeats::Seats(seats)) => {
+            sheet.write_number_with_format(row, col, seats.value(), &formats.integer)?;
+        }
+        PollOption::NotAvailable => {}
+    }
+    Ok(())
+}
+
+/// Converts a percentage to the fraction Excel formats as one, without the noise of widening the `f32` directly, so 19% is stored as 0.19 rather than 0.1899999976.
+fn fraction(percentage: f32) -> f64 {
+    let percentage: f64 = percentage.to_string().parse().unwrap_or(percentage as f64);
+    percentage / 100.0
+}
+
+fn excel_date(date: NaiveDate) -> Result<ExcelDateTime, SpreadsheetError> {
+    Ok(ExcelDateTime::from_ymd(
+        date.year() as u16,
+        date.month() as u8,
+        date.day() as u8,
+    )?)
+}
+
+/// Returns the party columns of a table, in alphabetical order.
+fn parties(table: &PollTable) -> Vec<String> {
+    let mut parties: Vec<String> = table
+        .polls
+        .iter()
+        .flat_map(|poll| poll.party_results.keys().cloned())
+        .collect();
+    parties.sort();
+    parties.dedup();
+    parties
+}
+
+/// Names a sheet after its jurisdiction, or its code where the name is too long for Excel, has disallowed characters or is taken.
+fn sheet_name(table: &PollTable, taken: &[String]) -> String {
+    let name = table.jurisdiction.name();
+    if name.chars().count() <= 31
+        && !name.contains(INVALID_SHEET_CHARACTERS)
+        && !taken.iter().any(|other| other == name)
+    {
+        name.to_string()
+    } else {
+        table.jurisdiction.code().to_string()
+    }
+}
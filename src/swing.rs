@@ -0,0 +1,162 @@
+//! Changes in party support since the previous election.
+use crate::average::PollAverage;
+use crate::elections::{ElectionHistory, ElectionResult};
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize)]
+/// A party's current support compared to the previous election, in percent.
+pub struct PartySwing {
+    party: String,
+    current: Option<f32>,
+    previous: Option<f32>,
+}
+
+impl PartySwing {
+    /// Returns the party column name.
+    pub fn party(&self) -> &str {
+        &self.party
+    }
+
+    /// Returns the party's current support, or [None] if it is not available.
+    pub fn current(&self) -> Option<f32> {
+        self.current
+    }
+
+    /// Returns the party's vote share at the previous election, or [None] if it did not stand.
+    pub fn previous(&self) -> Option<f32> {
+        self.previous
+    }
+
+    /// Returns whether the party did not stand at the previous election.
+    pub fn is_new(&self) -> bool {
+        self.previous.is_none()
+    }
+
+    /// Returns the change in percentage points, or [None] if the party did not stand or its current support is not available.
+    pub fn swing(&self) -> Option<f32> {
+        Some(self.current? - self.previous?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The swing of every party column relative to an election result.
+///
+/// Parties are aligned by column name. Election parties without a column count towards the rest of the vote,
+/// together with other parties and columns without a result.
+pub struct Swing {
+    parties: Vec<PartySwing>,
+}
+
+impl Swing {
+    fn new(current: BTreeMap<String, Option<f32>>, election: &ElectionResult) -> Self {
+        let parties = current
+            .into_iter()
+            .map(|(party, current)| {
+                let previous = election
+                    .party(&party)
+                    .and_then(|result| result.vote_share());
+                PartySwing {
+                    party,
+                    current,
+                    previous,
+                }
+            })
+            .collect();
+        Swing { parties }
+    }
+
+    /// Returns the swing of every party column, in alphabetical order.
+    pub fn parties(&self) -> &[PartySwing] {
+        &self.parties
+    }
+
+    /// Returns the swing of a party.
+    pub fn party(&self, party: &str) -> Option<&PartySwing> {
+        self.parties.iter().find(|swing| swing.party == party)
+    }
+
+    /// Returns the two-party (Butler) swing from `from` to `to`: half of `to`'s gain minus `from`'s gain.
+    /// Returns [None] if either party's swing is unknown.
+    pub fn two_party_swing(&self, from: &str, to: &str) -> Option<f32> {
+        let from = self.party(from)?.swing()?;
+        let to = self.party(to)?.swing()?;
+        Some((to - from) / 2.0)
+    }
+
+    /// Returns the Pedersen volatility index: half the sum of the absolute changes of all parties.
+    ///
+    /// Parties with a current result are included, with new parties counted from 0.
+    /// Everything else is combined into one remainder, so that votes moving to or from parties without a column are counted too.
+    pub fn pedersen(&self) -> f32 {
+        let known: Vec<(f32, f32)> = self
+            .parties
+            .iter()
+            .filter_map(|swing| Some((swing.current?, swing.previous.unwrap_or(0.0))))
+            .collect();
+        let changes: f32 = known
+            .iter()
+            .map(|(current, previous)| (current - previous).abs())
+            .sum();
+        let current_rest = 100.0 - known.iter().map(|(current, _)| current).sum::<f32>();
+        let previous_rest = 100.0 - known.iter().map(|(_, previous)| previous).sum::<f32>();
+        (changes + (current_rest - previous_rest).abs()) / 2.0
+    }
+}
+
+impl Poll {
+    /// Compares the poll's results with an election result. Results given as seats are treated as not available.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::elections::ElectionHistory;
+    /// let polls = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,New Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,20%,5%,45%";
+    /// let results = "Date,Scope,Turnout,First Party,Second Party,Old Party,Other
+    /// 2021-09-26,National,76.4%,25%,24%,6%,45%";
+    /// let poll_table = PollTable::from_str(polls, "de").unwrap();
+    /// let history = ElectionHistory::from_str(results, "de").unwrap();
+    /// let swing = poll_table.swing(0, &history).unwrap();
+    ///
+    /// assert_eq!(swing.party("First Party").unwrap().swing(), Some(5.0));
+    /// assert!(swing.party("New Party").unwrap().is_new());
+    /// assert_eq!(swing.two_party_swing("Second Party", "First Party"), Some(4.5));
+    /// assert_eq!(swing.pedersen(), 10.0);
+    /// ```
+    pub fn swing_from(&self, election: &ElectionResult) -> Swing {
+        let current = self
+            .party_results
+            .iter()
+            .map(|(party, result)| {
+                let value = match result {
+                    PollOption::Some(PercentageOrSeats::Percentage(share)) => Some(share.value()),
+                    _ => None,
+                };
+                (party.clone(), value)
+            })
+            .collect();
+        Swing::new(current, election)
+    }
+}
+
+impl PollAverage {
+    /// Compares the average with an election result.
+    pub fn swing_from(&self, election: &ElectionResult) -> Swing {
+        let current = self
+            .parties()
+            .iter()
+            .map(|(party, average)| (party.clone(), Some(average.value())))
+            .collect();
+        Swing::new(current, election)
+    }
+}
+
+impl PollTable {
+    /// Compares the poll at an index with the last election of the same scope before its fieldwork started.
+    /// Returns [None] if there is no such poll or election.
+    pub fn swing(&self, index: usize, history: &ElectionHistory) -> Option<Swing> {
+        let poll = self.polls.get(index)?;
+        let election = history.latest_before(&poll.scope, &poll.fieldwork_start)?;
+        Some(poll.swing_from(election))
+    }
+}
@@ -0,0 +1,474 @@
+//! Interactive terminal interface for browsing a [PollTableCollection].
+use crate::collection::PollTableCollection;
+use crate::duplicates::DuplicateOptions;
+use crate::outliers::OutlierOptions;
+use crate::query::PollQuery;
+use crate::resample::{Period, ResampleOptions};
+use crate::validation::ValidationProblem;
+use crate::{Poll, PollOption, PollTable, Scope};
+use chrono::NaiveDate;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Sparkline, Table, TableState, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+/// The most parties shown with a sparkline.
+const SPARKLINES: usize = 8;
+
+/// The columns before the party columns: firm, fieldwork, scope and sample size.
+const FIXED_COLUMNS: [&str; 4] = ["Firm", "Fieldwork", "Scope", "Sample"];
+
+const HELP: &str = "q quit  Tab jurisdiction  ←→ column  s sort  f firm  d dates  c scope  x clear";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Firm,
+    Dates,
+}
+
+struct App<'a> {
+    collection: &'a PollTableCollection,
+    table: usize,
+    column: usize,
+    sort: Option<(usize, bool)>,
+    firm: String,
+    scope: Option<Scope>,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    input: Option<(Input, String)>,
+    message: Option<String>,
+    state: TableState,
+    rows: Vec<usize>,
+    parties: Vec<String>,
+    warnings: HashMap<usize, Vec<String>>,
+}
+
+/// Opens the interface in the terminal and returns when the user quits.
+///
+/// Polls can be sorted by any column and filtered by firm, scope and date. Polls with validation problems,
+/// duplicates or outlying results are highlighted, and the trend of each party is shown as a weekly sparkline.
+pub fn run(collection: &PollTableCollection) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new(collection).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<'a> App<'a> {
+    fn new(collection: &'a PollTableCollection) -> Self {
+        let mut app = App {
+            collection,
+            table: 0,
+            column: 0,
+            sort: None,
+            firm: String::new(),
+            scope: None,
+            from: None,
+            until: None,
+            input: None,
+            message: None,
+            state: TableState::default(),
+            rows: Vec::new(),
+            parties: Vec::new(),
+            warnings: HashMap::new(),
+        };
+        app.load_table();
+        app
+    }
+
+    fn poll_table(&self) -> Option<&'a PollTable> {
+        self.collection.tables().get(self.table)
+    }
+
+    fn query(&self) -> PollQuery {
+        let mut query = PollQuery::new();
+        if !self.firm.is_empty() {
+            query = query.firm(&self.firm);
+        }
+        if let Some(scope) = self.scope {
+            query = query.scope(scope);
+        }
+        if let Some(from) = self.from {
+            query = query.from(from);
+        }
+        if let Some(until) = self.until {
+            query = query.until(until);
+        }
+        query
+    }
+
+    /// Recomputes the party columns and warnings after switching tables.
+    fn load_table(&mut self) {
+        self.parties.clear();
+        self.warnings.clear();
+        if let Some(poll_table) = self.poll_table() {
+            let parties: BTreeSet<&String> = poll_table
+                .polls
+                .iter()
+                .flat_map(|poll| poll.party_results.keys())
+                .collect();
+            self.parties = parties.into_iter().cloned().collect();
+
+            for problem in poll_table.validate() {
+                let ValidationProblem::DuplicatePollId { indices, .. } = &problem;
+                for index in indices {
+                    self.warn(*index, problem.to_string());
+                }
+            }
+            for duplicate in poll_table.find_duplicates(&DuplicateOptions::new()) {
+                let message = format!(
+                    "Possible duplicate of poll {} ({:.0}% similar)",
+                    duplicate.first.index,
+                    duplicate.similarity * 100.0
+                );
+                self.warn(duplicate.second.index, message);
+            }
+            for outlier in poll_table.outliers(&OutlierOptions::new()) {
+                let message = format!("Outlier, score {:.1}", outlier.score());
+                self.warn(outlier.index(), message);
+            }
+        }
+        self.column = self
+            .column
+            .min(FIXED_COLUMNS.len() + self.parties.len() - 1);
+        self.refresh();
+    }
+
+    fn warn(&mut self, index: usize, message: String) {
+        self.warnings.entry(index).or_default().push(message);
+    }
+
+    /// Recomputes the visible rows after changing filters or sorting.
+    fn refresh(&mut self) {
+        let Some(poll_table) = self.poll_table() else {
+            self.rows.clear();
+            return;
+        };
+        let mut rows = poll_table.query(&self.query());
+        if let Some((column, descending)) = self.sort {
+            rows.sort_by(|a, b| {
+                let ordering = self.compare(poll_table, column, *a, *b);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        self.rows = rows;
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(if self.rows.is_empty() {
+            None
+        } else {
+            Some(selected.min(self.rows.len() - 1))
+        });
+    }
+
+    fn compare(&self, poll_table: &PollTable, column: usize, a: usize, b: usize) -> Ordering {
+        let (a, b) = (&poll_table.polls[a], &poll_table.polls[b]);
+        match column {
+            0 => a
+                .polling_firm
+                .to_lowercase()
+                .cmp(&b.polling_firm.to_lowercase()),
+            1 => (a.fieldwork_end, a.fieldwork_start).cmp(&(b.fieldwork_end, b.fieldwork_start)),
+            2 => a.scope.to_string().cmp(&b.scope.to_string()),
+            3 => a
+                .effective_sample_size()
+                .total_cmp(&b.effective_sample_size()),
+            _ => {
+                let party = &self.parties[column - FIXED_COLUMNS.len()];
+                let value = |poll: &Poll| match poll.party_results.get(party) {
+                    Some(PollOption::Some(result)) => result.value(),
+                    _ => f32::NEG_INFINITY,
+                };
+                value(a).total_cmp(&value(b))
+            }
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some((field, mut text)) = self.input.take() {
+                match key.code {
+                    KeyCode::Enter => self.apply_input(field, &text),
+                    KeyCode::Esc => {}
+                    KeyCode::Backspace => {
+                        text.pop();
+                        self.input = Some((field, text));
+                    }
+                    KeyCode::Char(c) => {
+                        text.push(c);
+                        self.input = Some((field, text));
+                    }
+                    _ => self.input = Some((field, text)),
+                }
+                continue;
+            }
+
+            self.message = None;
+            let columns = FIXED_COLUMNS.len() + self.parties.len();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::PageDown => self.state.scroll_down_by(20),
+                KeyCode::PageUp => self.state.scroll_up_by(20),
+                KeyCode::Home => self.state.select_first(),
+                KeyCode::End => self.state.select_last(),
+                KeyCode::Right | KeyCode::Char('l') => self.column = (self.column + 1) % columns,
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.column = (self.column + columns - 1) % columns
+                }
+                KeyCode::Tab | KeyCode::BackTab if !self.collection.is_empty() => {
+                    let len = self.collection.len();
+                    self.table = if key.code == KeyCode::Tab {
+                        (self.table + 1) % len
+                    } else {
+                        (self.table + len - 1) % len
+                    };
+                    self.sort = None;
+                    self.state.select(Some(0));
+                    self.load_table();
+                }
+                KeyCode::Char('s') => {
+                    self.sort = match self.sort {
+                        Some((column, false)) if column == self.column => Some((column, true)),
+                        _ => Some((self.column, false)),
+                    };
+                    self.refresh();
+                }
+                KeyCode::Char('f') => self.input = Some((Input::Firm, self.firm.clone())),
+                KeyCode::Char('d') => self.input = Some((Input::Dates, String::new())),
+                KeyCode::Char('c') => {
+                    self.scope = match self.scope {
+                        None => Some(Scope::National),
+                        Some(Scope::National) => Some(Scope::European),
+                        Some(Scope::European) => None,
+                    };
+                    self.refresh();
+                }
+                KeyCode::Char('x') => {
+                    self.firm.clear();
+                    self.scope = None;
+                    self.from = None;
+                    self.until = None;
+                    self.refresh();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Applies a firm filter, or a date range written as "from..until" where either date may be left out.
+    fn apply_input(&mut self, field: Input, text: &str) {
+        match field {
+            Input::Firm => self.firm = text.trim().to_string(),
+            Input::Dates => {
+                let (from, until) = text.split_once("..").unwrap_or((text, ""));
+                let parse = |s: &str| {
+                    let s = s.trim();
+                    (!s.is_empty())
+                        .then(|| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+                        .transpose()
+                };
+                match (parse(from), parse(until)) {
+                    (Ok(from), Ok(until)) => {
+                        self.from = from;
+                        self.until = until;
+                    }
+                    _ => self.message = Some(String::from("Dates must be YYYY-MM-DD..YYYY-MM-DD")),
+                }
+            }
+        }
+        self.refresh();
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let sparklines = self.parties.len().min(SPARKLINES) as u16;
+        let [tabs, table, trends, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(sparklines + 2),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let titles = self
+            .collection
+            .tables()
+            .iter()
+            .map(|table| table.jurisdiction.name());
+        frame.render_widget(Tabs::new(titles).select(self.table), tabs);
+
+        self.draw_table(frame, table);
+        self.draw_trends(frame, trends);
+        frame.render_widget(Paragraph::new(self.status_line()), status);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(poll_table) = self.poll_table() else {
+            frame.render_widget(Paragraph::new("No poll tables found"), area);
+            return;
+        };
+
+        let header = FIXED_COLUMNS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(self.parties.iter().cloned())
+            .enumerate()
+            .map(|(i, mut name)| {
+                if let Some((column, descending)) = self.sort {
+                    if column == i {
+                        name.push(if descending { '▼' } else { '▲' });
+                    }
+                }
+                let style = if i == self.column {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new().add_modifier(Modifier::BOLD)
+                };
+                Cell::from(name).style(style)
+            });
+
+        let rows = self.rows.iter().map(|&index| {
+            let poll = &poll_table.polls[index];
+            let cells = [
+                poll.polling_firm.clone(),
+                format!("{} – {}", poll.fieldwork_start, poll.fieldwork_end),
+                poll.scope.to_string(),
+                poll.sample_size.to_string(),
+            ]
+            .into_iter()
+            .chain(self.parties.iter().map(|party| {
+                poll.party_results
+                    .get(party)
+                    .map_or_else(String::new, |result| result.to_string())
+            }));
+            let row = Row::new(cells);
+            if self.warnings.contains_key(&index) {
+                row.style(Style::new().fg(Color::Yellow))
+            } else {
+                row
+            }
+        });
+
+        let widths = [
+            Constraint::Length(24),
+            Constraint::Length(25),
+            Constraint::Length(9),
+            Constraint::Length(8),
+        ]
+        .into_iter()
+        .chain(
+            self.parties
+                .iter()
+                .map(|party| Constraint::Length(party.chars().count().max(6) as u16 + 1)),
+        );
+
+        let table = Table::new(rows, widths)
+            .header(Row::new(header))
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " {} of {} polls ",
+                self.rows.len(),
+                poll_table.polls.len()
+            )))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn draw_trends(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Weekly trend ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let Some(poll_table) = self.poll_table() else {
+            return;
+        };
+
+        // The trend is of the selected scope, or of national polls when no scope is selected.
+        let options =
+            ResampleOptions::new(Period::Week).scope(self.scope.unwrap_or(Scope::National));
+        let weekly = poll_table.filtered(&self.query()).resample(&options);
+        let label_width = 16;
+        let rows = Layout::vertical(vec![
+            Constraint::Length(1);
+            self.parties.len().min(SPARKLINES)
+        ])
+        .split(inner);
+        for (party, row) in self.parties.iter().zip(rows.iter()) {
+            let [label, chart] =
+                Layout::horizontal([Constraint::Length(label_width), Constraint::Min(1)])
+                    .areas(*row);
+            let series = weekly.series(party);
+            let latest = series.iter().rev().find_map(|(_, value)| *value);
+            let text = match latest {
+                Some(value) => format!("{party} {value:.1}%"),
+                None => party.clone(),
+            };
+            frame.render_widget(Paragraph::new(text), label);
+
+            // Show the most recent weeks that fit, in tenths of a percentage point.
+            let skip = series.len().saturating_sub(chart.width as usize);
+            let data: Vec<Option<u64>> = series[skip..]
+                .iter()
+                .map(|(_, value)| value.map(|value| (value * 10.0).round() as u64))
+                .collect();
+            frame.render_widget(Sparkline::default().data(&data), chart);
+        }
+    }
+
+    fn status_line(&self) -> Line<'static> {
+        if let Some((field, text)) = &self.input {
+            let prompt = match field {
+                Input::Firm => "Firm: ",
+                Input::Dates => "Dates (from..until): ",
+            };
+            return Line::from(vec![
+                Span::raw(prompt),
+                Span::raw(text.clone()),
+                Span::raw("█"),
+            ]);
+        }
+        if let Some(message) = &self.message {
+            return Line::styled(message.clone(), Style::new().fg(Color::Red));
+        }
+        let selected = self
+            .state
+            .selected()
+            .and_then(|row| self.rows.get(row))
+            .and_then(|index| self.warnings.get(index));
+        if let Some(warnings) = selected {
+            return Line::styled(warnings.join("; "), Style::new().fg(Color::Yellow));
+        }
+
+        let mut filters = Vec::new();
+        if !self.firm.is_empty() {
+            filters.push(format!("firm: {}", self.firm));
+        }
+        if let Some(scope) = self.scope {
+            filters.push(format!("scope: {scope}"));
+        }
+        if self.from.is_some() || self.until.is_some() {
+            let date = |date: Option<NaiveDate>| date.map_or_else(String::new, |d| d.to_string());
+            filters.push(format!("dates: {}..{}", date(self.from), date(self.until)));
+        }
+        if filters.is_empty() {
+            Line::raw(HELP)
+        } else {
+            Line::raw(format!("{}  |  {HELP}", filters.join(", ")))
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Checks for problems in poll data which do not prevent it from being parsed.
+use crate::identity::PollId;
+use crate::PollTable;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize)]
+/// A problem found by [PollTable::validate].
+pub enum ValidationProblem {
+    /// Several polls share the same polling firm, commissioners, fieldwork dates and scope, and so the same [PollId].
+    DuplicatePollId {
+        /// The shared id.
+        id: PollId,
+        /// The indices of the polls sharing the id.
+        indices: Vec<usize>,
+    },
+}
+
+impl fmt::Display for ValidationProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationProblem::DuplicatePollId { id, indices } => {
+                let indices: Vec<String> = indices.iter().map(ToString::to_string).collect();
+                write!(f, "Polls {} share the id {id}", indices.join(", "))
+            }
+        }
+    }
+}
+
+impl PollTable {
+    /// Checks the [PollTable] for problems, and returns them in order of first occurrence.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::validation::ValidationProblem;
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,21%,79%
+    /// Epic Polling,The Daily Snail,2024-03-06,2024-03-08,National,2054,Provided,Not Available,1%,22%,78%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let problems = poll_table.validate();
+    ///
+    /// assert!(matches!(&problems[0], ValidationProblem::DuplicatePollId { indices, .. } if indices == &[0, 1]));
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationProblem> {
+        let mut ids: BTreeMap<PollId, Vec<usize>> = BTreeMap::new();
+        for (i, poll) in self.polls.iter().enumerate() {
+            ids.entry(poll.id()).or_default().push(i);
+        }
+
+        let mut problems: Vec<ValidationProblem> = ids
+            .into_iter()
+            .filter(|(_, indices)| indices.len() > 1)
+            .map(|(id, indices)| ValidationProblem::DuplicatePollId { id, indices })
+            .collect();
+        problems.sort_by_key(|problem| match problem {
+            ValidationProblem::DuplicatePollId { indices, .. } => indices[0],
+        });
+        problems
+    }
+}
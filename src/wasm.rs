@@ -0,0 +1,274 @@
+//! WebAssembly bindings, built with `wasm-pack build --features wasm --no-default-features`.
+//!
+//! ```js
+//! import { PollTable } from "europe-elects-csv";
+//!
+//! const table = PollTable.fromString(await (await fetch("/data/de.csv")).text(), "de");
+//! const polls = table.polls();
+//! const average = table.average();
+//! const seats = table.projectSeats(630, "sainte-lague", 5);
+//! ```
+//!
+//! Polls, averages and seat projections are returned as plain JavaScript objects, with dates written as YYYY-MM-DD.
+use crate::average::AverageOptions;
+use crate::query::PollQuery;
+use crate::seats::{AllocationMethod, ElectoralSystem, Quota};
+use crate::{PercentageOrSeats, Poll, PollOption, PollTable, Scope};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PartyResult {
+    value: f32,
+    unit: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PollObject<'a> {
+    index: usize,
+    id: String,
+    polling_firm: &'a str,
+    commissioners: Option<&'a str>,
+    fieldwork_start: NaiveDate,
+    fieldwork_end: NaiveDate,
+    scope: String,
+    sample_size: Option<f32>,
+    results: BTreeMap<&'a str, Option<PartyResult>>,
+    other: Option<PartyResult>,
+}
+
+fn party_result(result: &PollOption<PercentageOrSeats>) -> Option<PartyResult> {
+    match result {
+        PollOption::Some(PercentageOrSeats::Percentage(share)) => Some(PartyResult {
+            value: share.value(),
+            unit: "percent",
+        }),
+        PollOption::Some(PercentageOrSeats::Seats(seats)) => Some(PartyResult {
+            value: seats.value(),
+            unit: "seats",
+        }),
+        PollOption::NotAvailable => None,
+    }
+}
+
+impl<'a> PollObject<'a> {
+    fn new(index: usize, poll: &'a Poll) -> Self {
+        PollObject {
+            index,
+            id: poll.id().to_string(),
+            polling_firm: &poll.polling_firm,
+            commissioners: match &poll.commissioners {
+                PollOption::Some(commissioners) => Some(commissioners),
+                PollOption::NotAvailable => None,
+            },
+            fieldwork_start: poll.fieldwork_start,
+            fieldwork_end: poll.fieldwork_end,
+            scope: poll.scope.to_string(),
+            sample_size: match poll.sample_size {
+                PollOption::Some(size) => Some(size),
+                PollOption::NotAvailable => None,
+            },
+            results: poll
+                .party_results
+                .iter()
+                .map(|(party, result)| (party.as_str(), party_result(result)))
+                .collect(),
+            other: party_result(&poll.other),
+        }
+    }
+}
+
+/// Converts a value to a plain JavaScript object, with maps as objects rather than `Map`s.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    value
+        .serialize(&serializer)
+        .map_err(|error| JsError::new(&error.to_string()))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, JsError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| JsError::new(&format!("invalid date, expected YYYY-MM-DD: {date}")))
+}
+
+fn parse_scope(scope: &str) -> Result<Scope, JsError> {
+    match scope.to_lowercase().as_str() {
+        "national" => Ok(Scope::National),
+        "european" => Ok(Scope::European),
+        _ => Err(JsError::new(&format!("invalid scope: {scope}"))),
+    }
+}
+
+#[wasm_bindgen(js_name = PollTable)]
+/// A [PollTable] of one jurisdiction.
+pub struct WasmPollTable {
+    table: PollTable,
+}
+
+#[wasm_bindgen(js_class = PollTable)]
+impl WasmPollTable {
+    /// Parses Europe Elects .csv data for a jurisdiction code, such as "de".
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn from_string(csv: &str, jurisdiction: &str) -> Result<WasmPollTable, JsError> {
+        let table = PollTable::from_str(csv, jurisdiction)?;
+        Ok(WasmPollTable { table })
+    }
+
+    /// Returns the jurisdiction code, such as "de".
+    #[wasm_bindgen(getter)]
+    pub fn jurisdiction(&self) -> String {
+        self.table.jurisdiction.code().to_string()
+    }
+
+    /// Returns the jurisdiction's name.
+    #[wasm_bindgen(getter, js_name = jurisdictionName)]
+    pub fn jurisdiction_name(&self) -> String {
+        self.table.jurisdiction.name().to_string()
+    }
+
+    /// Returns the number of polls.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.table.polls.len()
+    }
+
+    /// Returns the party columns, in alphabetical order.
+    pub fn parties(&self) -> Vec<String> {
+        let mut parties: Vec<String> = self
+            .table
+            .polls
+            .iter()
+            .flat_map(|poll| poll.party_results.keys().cloned())
+            .collect();
+        parties.sort();
+        parties.dedup();
+        parties
+    }
+
+    /// Returns the poll at an index, where 0 is the newest, as a plain object.
+    pub fn poll(&self, index: usize) -> Result<JsValue, JsError> {
+        let poll = self
+            .table
+            .polls
+            .get(index)
+            .ok_or_else(|| JsError::new("poll index out of range"))?;
+        to_js(&PollObject::new(index, poll))
+    }
+
+    /// Returns every poll, newest first, as an array of plain objects.
+    pub fn polls(&self) -> Result<JsValue, JsError> {
+        let polls: Vec<PollObject> = self
+            .table
+            .polls
+            .iter()
+            .enumerate()
+            .map(|(i, poll)| PollObject::new(i, poll))
+            .collect();
+        to_js(&polls)
+    }
+
+    /// Returns a table with only the polls matching every given condition, as in [PollQuery].
+    pub fn filter(
+        &self,
+        firm: Option<String>,
+        scope: Option<String>,
+        from: Option<String>,
+        until: Option<String>,
+    ) -> Result<WasmPollTable, JsError> {
+        let mut query = PollQuery::new();
+        if let Some(firm) = firm {
+            query = query.firm(&firm);
+        }
+        if let Some(scope) = scope {
+            query = query.scope(parse_scope(&scope)?);
+        }
+        if let Some(from) = from {
+            query = query.from(parse_date(&from)?);
+        }
+        if let Some(until) = until {
+            query = query.until(parse_date(&until)?);
+        }
+        Ok(WasmPollTable {
+            table: self.table.filtered(&query),
+        })
+    }
+
+    /// Returns the weighted average of polls of a scope, "national" by default, at a date, by default the end of the latest fieldwork of that scope, as a plain object.
+    pub fn average(
+        &self,
+        date: Option<String>,
+        window_days: Option<i32>,
+        centered: Option<bool>,
+        scope: Option<String>,
+    ) -> Result<JsValue, JsError> {
+        to_js(&self.compute_average(date, window_days, centered, scope)?)
+    }
+
+    /// Allocates seats with "dhondt" (the default), "sainte-lague", "hare" or "droop" from the average at a date, as a plain object from party to seats.
+    /// The average is of polls of a scope, "national" by default.
+    #[wasm_bindgen(js_name = projectSeats)]
+    pub fn project_seats(
+        &self,
+        seats: u32,
+        method: Option<String>,
+        threshold: Option<f32>,
+        date: Option<String>,
+        window_days: Option<i32>,
+        scope: Option<String>,
+    ) -> Result<JsValue, JsError> {
+        let method = match method.as_deref() {
+            None | Some("dhondt") => AllocationMethod::DHondt,
+            Some("sainte-lague") => AllocationMethod::SainteLague,
+            Some("hare") => AllocationMethod::LargestRemainder(Quota::Hare),
+            Some("droop") => AllocationMethod::LargestRemainder(Quota::Droop),
+            Some(method) => return Err(JsError::new(&format!("invalid method: {method}"))),
+        };
+        let average = self.compute_average(date, window_days, None, scope)?;
+        let shares: HashMap<String, f32> = average
+            .parties()
+            .iter()
+            .map(|(party, average)| (party.clone(), average.value()))
+            .collect();
+        let projection: BTreeMap<String, u32> =
+            ElectoralSystem::new(seats, method, threshold.unwrap_or(0.0))
+                .allocate(&shares)
+                .into_iter()
+                .collect();
+        to_js(&projection)
+    }
+}
+
+impl WasmPollTable {
+    fn compute_average(
+        &self,
+        date: Option<String>,
+        window_days: Option<i32>,
+        centered: Option<bool>,
+        scope: Option<String>,
+    ) -> Result<crate::average::PollAverage, JsError> {
+        let scope = scope
+            .map(|scope| parse_scope(&scope))
+            .transpose()?
+            .unwrap_or(Scope::National);
+        let date = match date {
+            Some(date) => parse_date(&date)?,
+            None => self
+                .table
+                .polls
+                .iter()
+                .filter(|poll| poll.scope == scope)
+                .map(|poll| poll.fieldwork_end)
+                .max()
+                .ok_or_else(|| JsError::new("the table has no polls"))?,
+        };
+        let options = AverageOptions::new()
+            .window_days(window_days.unwrap_or(28) as i64)
+            .centered(centered.unwrap_or(false))
+            .scope(scope);
+        Ok(self.table.average_at(&date, &options))
+    }
+}
@@ -0,0 +1,326 @@
+//! Watching a directory of Europe Elects .csv files for new, corrected and removed polls.
+use crate::diff::{FieldChange, PollRef, PollTableDiff};
+use crate::errors::{PollTableTryFromPathError, WatchError};
+use crate::{Jurisdiction, PollTable};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// What happened to a poll.
+pub enum WatchEventKind {
+    /// The poll was not in the previous version of its file.
+    New,
+    /// The poll was in the previous version of its file, with different results or metadata.
+    Corrected,
+    /// The poll is no longer in its file.
+    Removed,
+}
+
+impl WatchEventKind {
+    fn slug(&self) -> &'static str {
+        match self {
+            WatchEventKind::New => "new",
+            WatchEventKind::Corrected => "corrected",
+            WatchEventKind::Removed => "removed",
+        }
+    }
+}
+
+impl fmt::Display for WatchEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchEventKind::New => write!(f, "New poll"),
+            WatchEventKind::Corrected => write!(f, "Corrected poll"),
+            WatchEventKind::Removed => write!(f, "Removed poll"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A change to one poll, as reported by a [Watcher].
+pub struct WatchEvent {
+    /// What happened to the poll.
+    pub kind: WatchEventKind,
+    /// The code of the poll's jurisdiction, such as "de".
+    pub jurisdiction: &'static str,
+    /// The poll. Its index is in the new version of the file, except for removed polls.
+    pub poll: PollRef,
+    /// The fields that changed, for corrected polls.
+    pub changes: Vec<FieldChange>,
+    /// When the change was noticed.
+    pub detected: DateTime<Utc>,
+}
+
+impl WatchEvent {
+    /// Turns a [PollTableDiff] of a jurisdiction's file into events, in the order new, corrected, removed.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::watch::{WatchEvent, WatchEventKind};
+    /// let old = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,30%,70%";
+    /// let new = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// New Polling,Not Available,2024-03-10,2024-03-12,National,1000,Provided,Not Available,1%,32%,68%
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1000,Provided,Not Available,1%,31%,69%";
+    /// let old = PollTable::from_str(old, "de").unwrap();
+    /// let new = PollTable::from_str(new, "de").unwrap();
+    /// let events = WatchEvent::from_diff("de", &PollTable::diff(&old, &new), chrono::Utc::now());
+    ///
+    /// assert_eq!(events.len(), 2);
+    /// assert_eq!(events[0].kind, WatchEventKind::New);
+    /// assert_eq!(events[0].poll.polling_firm, "New Polling");
+    /// assert_eq!(events[1].kind, WatchEventKind::Corrected);
+    /// assert_eq!(events[1].poll.index, 1);
+    /// assert_eq!(events[1].changes[0].new, "31%");
+    /// ```
+    pub fn from_diff(
+        jurisdiction: &'static str,
+        diff: &PollTableDiff,
+        detected: DateTime<Utc>,
+    ) -> Vec<WatchEvent> {
+        let event = |kind, poll: PollRef, changes| WatchEvent {
+            kind,
+            jurisdiction,
+            poll,
+            changes,
+            detected,
+        };
+        let new = diff
+            .added
+            .iter()
+            .map(|poll| event(WatchEventKind::New, poll.clone(), Vec::new()));
+        let corrected = diff.changed.iter().map(|poll_diff| {
+            let mut poll = poll_diff.old.clone();
+            poll.index = poll_diff.new_index;
+            event(WatchEventKind::Corrected, poll, poll_diff.changes.clone())
+        });
+        let removed = diff
+            .removed
+            .iter()
+            .map(|poll| event(WatchEventKind::Removed, poll.clone(), Vec::new()));
+        new.chain(corrected).chain(removed).collect()
+    }
+
+    /// Returns the event as a single line of JSON.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("WatchEvent should serialize to JSON")
+    }
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}: {}", self.kind, self.jurisdiction, self.poll)
+    }
+}
+
+/// The size and modification time of a file, used to notice changes.
+type FileState = (u64, Option<SystemTime>);
+
+/// Watches the .csv files of a directory by checking them at intervals.
+///
+/// Files whose name is not a Europe Elects jurisdiction code are ignored. A file that cannot be parsed, for instance because it is still being written,
+/// keeps its previous version and is checked again next time.
+pub struct Watcher {
+    dir: String,
+    files: BTreeMap<PathBuf, (FileState, Option<PollTable>)>,
+}
+
+impl Watcher {
+    /// Loads the current version of every file in a directory, without reporting any events.
+    pub fn new(dir: &str) -> Result<Watcher, WatchError> {
+        let mut watcher = Watcher {
+            dir: dir.to_string(),
+            files: BTreeMap::new(),
+        };
+        watcher.check()?;
+        Ok(watcher)
+    }
+
+    /// Returns the tables loaded so far, in order of file name.
+    pub fn tables(&self) -> Vec<&PollTable> {
+        self.files
+            .values()
+            .filter_map(|(_, table)| table.as_ref())
+            .collect()
+    }
+
+    /// Reparses the files that were added, removed or modified since the last check, and returns the changes to their polls.
+    /// Polls in new files are reported as new, and polls in deleted files as removed.
+    pub fn check(&mut self) -> Result<Vec<WatchEvent>, WatchError> {
+        let detected = Utc::now();
+        let mut current: BTreeMap<PathBuf, FileState> = BTreeMap::new();
+        // Entries that vanish or cannot be read while listing are skipped, and checked again next time.
+        for entry in std::fs::read_dir(&self.dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("csv") {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                current.insert(path, (metadata.len(), metadata.modified().ok()));
+            }
+        }
+
+        let mut events = Vec::new();
+        let deleted: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in deleted {
+            if let Some((_, Some(old))) = self.files.remove(&path) {
+                let diff = PollTable::diff(&old, &empty(old.jurisdiction));
+                events.extend(WatchEvent::from_diff(
+                    old.jurisdiction.code(),
+                    &diff,
+                    detected,
+                ));
+            }
+        }
+
+        for (path, state) in current {
+            if self.files.get(&path).map(|(old_state, _)| *old_state) == Some(state) {
+                continue;
+            }
+            let Some(file) = path.to_str() else {
+                continue;
+            };
+            let table = match PollTable::try_from_path(file) {
+                Ok(table) => table,
+                Err(PollTableTryFromPathError::InvalidJurisdictionError) => {
+                    self.files.insert(path, (state, None));
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            let diff = match self.files.get(&path) {
+                Some((_, Some(old))) => PollTable::diff(old, &table),
+                _ => PollTable::diff(&empty(table.jurisdiction), &table),
+            };
+            events.extend(WatchEvent::from_diff(
+                table.jurisdiction.code(),
+                &diff,
+                detected,
+            ));
+            self.files.insert(path, (state, Some(table)));
+        }
+        Ok(events)
+    }
+
+    /// Checks the directory at a fixed interval until the callback returns false or an error, passing it the watcher and the events of each check that found changes.
+    pub fn run<F>(mut self, interval: Duration, mut callback: F) -> Result<(), WatchError>
+    where
+        F: FnMut(&Watcher, &[WatchEvent]) -> Result<bool, WatchError>,
+    {
+        loop {
+            std::thread::sleep(interval);
+            let events = self.check()?;
+            if !events.is_empty() && !callback(&self, &events)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn empty(jurisdiction: Jurisdiction) -> PollTable {
+    PollTable::new(Vec::new(), jurisdiction)
+}
+
+#[derive(Debug, Clone)]
+/// An Atom feed of [WatchEvent]s, keeping the most recent entries.
+pub struct AtomFeed {
+    title: String,
+    max_entries: usize,
+    entries: VecDeque<WatchEvent>,
+}
+
+impl AtomFeed {
+    /// Creates an empty feed, keeping at most 100 entries.
+    pub fn new(title: &str) -> Self {
+        AtomFeed {
+            title: title.to_string(),
+            max_entries: 100,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Sets the number of entries to keep.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self.entries.truncate(max_entries);
+        self
+    }
+
+    /// Adds events to the top of the feed, dropping the oldest entries beyond the limit.
+    pub fn push(&mut self, events: &[WatchEvent]) {
+        for event in events {
+            self.entries.push_front(event.clone());
+        }
+        self.entries.truncate(self.max_entries);
+    }
+
+    /// Returns the feed as an Atom XML document.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::watch::{AtomFeed, WatchEvent};
+    /// let new = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Other
+    /// Smith & Sons,Not Available,2024-03-10,2024-03-12,National,1000,Provided,Not Available,1%,32%,68%";
+    /// let old = PollTable::from_str("Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,Other", "de").unwrap();
+    /// let new = PollTable::from_str(new, "de").unwrap();
+    /// let mut feed = AtomFeed::new("German polls");
+    /// feed.push(&WatchEvent::from_diff("de", &PollTable::diff(&old, &new), chrono::Utc::now()));
+    /// let xml = feed.to_xml();
+    ///
+    /// assert!(xml.contains("<title>German polls</title>"));
+    /// assert!(xml.contains("New poll in de: Smith &amp; Sons"));
+    /// ```
+    pub fn to_xml(&self) -> String {
+        let updated = self
+            .entries
+            .front()
+            .map(|event| event.detected)
+            .unwrap_or_else(Utc::now);
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <title>{}</title>\n  <id>urn:europe-elects-csv:feed:{}</id>\n  <updated>{}</updated>\n",
+            escape(&self.title),
+            escape(&self.title.to_lowercase().replace(' ', "-")),
+            updated.to_rfc3339(),
+        );
+        for event in &self.entries {
+            let summary: Vec<String> = event
+                .changes
+                .iter()
+                .map(|change| format!("{}: {} -> {}", change.field, change.old, change.new))
+                .collect();
+            xml.push_str(&format!(
+                "  <entry>\n    <title>{}</title>\n    <id>urn:europe-elects-csv:{}:{}:{}:{}</id>\n    <updated>{}</updated>\n    <summary>{}</summary>\n  </entry>\n",
+                escape(&event.to_string()),
+                event.jurisdiction,
+                event.poll.id,
+                event.kind.slug(),
+                event.detected.timestamp(),
+                event.detected.to_rfc3339(),
+                escape(&summary.join("; ")),
+            ));
+        }
+        xml.push_str("</feed>\n");
+        xml
+    }
+
+    /// Writes the feed to a file, replacing it.
+    pub fn save(&self, path: &str) -> Result<(), WatchError> {
+        std::fs::write(path, self.to_xml())?;
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
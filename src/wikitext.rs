@@ -0,0 +1,768 @@
+//! Wikipedia opinion polling tables, written from a [PollTable] and read back into a [RawPollTable] to cross-check the two sources.
+use crate::errors::{ColumnMappingError, WikitableError};
+use crate::party::{is_dark, parse_color, PartyMetadata};
+use crate::{Percentage, PercentageOrSeats, Poll, PollOption, PollTable, RawPollTable, Scope};
+use chrono::{Datelike, NaiveDate};
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Written in cells whose value is not available, as on Wikipedia.
+const NOT_AVAILABLE: &str = "–";
+
+/// Builds the wikitext of a table of polls in the style of Wikipedia's opinion polling pages.
+///
+/// Each poll is one row, newest first, with its fieldwork dates, polling firm, commissioner, sample size, one column per party, others and the lead of the first party over the second.
+/// The leading result is in bold. With [PartyMetadata], the party headers get a row of color bars, and the leading result and the lead are shaded in the leader's color.
+/// ```
+/// use europe_elects_csv::*;
+/// use europe_elects_csv::wikitext::Wikitable;
+/// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,First Party,Second Party,Other
+/// Epic Polling,The Daily Snail,2024-02-28,2024-03-02,National,1204,Provided,Not Available,1%,32%,27.5%,40.5%";
+/// let poll_table = PollTable::from_str(example, "de").unwrap();
+/// let wikitext = Wikitable::new(&poll_table).to_wikitext();
+///
+/// assert!(wikitext.contains("| data-sort-value=\"2024-03-02\" | 28 Feb – 2 Mar 2024\n| Epic Polling\n| The Daily Snail\n| 1,204\n"));
+/// assert!(wikitext.contains("| '''32'''\n| 27.5\n| 40.5\n| 4.5\n"));
+/// ```
+pub struct Wikitable<'a> {
+    poll_table: &'a PollTable,
+    metadata: Option<&'a PartyMetadata>,
+    parties: Option<Vec<String>>,
+}
+
+impl<'a> Wikitable<'a> {
+    /// Creates a table of every poll and party in a [PollTable].
+    pub fn new(poll_table: &'a PollTable) -> Self {
+        Wikitable {
+            poll_table,
+            metadata: None,
+            parties: None,
+        }
+    }
+
+    /// Colors party headers and leads by the colors in their metadata.
+    pub fn with_metadata(mut self, metadata: &'a PartyMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Sets the party columns and their order, leaving out parties not listed.
+    /// By default, every party is shown, ordered by its result in the newest poll.
+    pub fn parties(mut self, parties: &[&str]) -> Self {
+        self.parties = Some(parties.iter().map(|party| party.to_string()).collect());
+        self
+    }
+
+    fn party_columns(&self) -> Vec<String> {
+        if let Some(parties) = &self.parties {
+            return parties.clone();
+        }
+        let polls = &self.poll_table.polls;
+        let mut parties: Vec<String> = polls
+            .iter()
+            .flat_map(|poll| poll.party_results.keys().cloned())
+            .collect();
+        parties.sort();
+        parties.dedup();
+        let newest = |party: &String| {
+            polls
+                .first()
+                .and_then(|poll| result_value(poll.party_results.get(party)?))
+                .unwrap_or(f32::NEG_INFINITY)
+        };
+        parties.sort_by(|a, b| newest(b).total_cmp(&newest(a)));
+        parties
+    }
+
+    fn color(&self, party: &str) -> Option<&str> {
+        self.metadata?.get(party)?.color()
+    }
+
+    /// Returns the table as wikitext.
+    pub fn to_wikitext(&self) -> String {
+        let parties = self.party_columns();
+        let colors: Vec<Option<&str>> = parties.iter().map(|party| self.color(party)).collect();
+        let rowspan = if self.metadata.is_some() {
+            "rowspan=\"2\" | "
+        } else {
+            ""
+        };
+
+        let mut wikitext =
+            String::from("{| class=\"wikitable sortable\" style=\"text-align:center;\"\n");
+        for header in [
+            "Fieldwork date",
+            "Polling firm",
+            "Commissioner",
+            "Sample size",
+        ] {
+            let _ = writeln!(wikitext, "! {rowspan}{header}");
+        }
+        for party in &parties {
+            let _ = writeln!(wikitext, "! {party}");
+        }
+        let _ = writeln!(wikitext, "! Others");
+        let _ = writeln!(wikitext, "! {rowspan}Lead");
+        if self.metadata.is_some() {
+            wikitext.push_str("|-\n");
+            for color in &colors {
+                match color {
+                    Some(color) => {
+                        let _ = writeln!(wikitext, "! style=\"background:{color};\" |");
+                    }
+                    None => wikitext.push_str("! |\n"),
+                }
+            }
+            wikitext.push_str("! |\n");
+        }
+
+        for poll in &self.poll_table.polls {
+            wikitext.push_str("|-\n");
+            let _ = writeln!(
+                wikitext,
+                "| data-sort-value=\"{}\" | {}",
+                poll.fieldwork_end,
+                fieldwork_dates(poll.fieldwork_start, poll.fieldwork_end)
+            );
+            let _ = writeln!(wikitext, "| {}", poll.polling_firm);
+            match &poll.commissioners {
+                PollOption::Some(commissioners) => {
+                    let _ = writeln!(wikitext, "| {commissioners}");
+                }
+                PollOption::NotAvailable => {
+                    let _ = writeln!(wikitext, "| {NOT_AVAILABLE}");
+                }
+            }
+            match poll.sample_size {
+                PollOption::Some(size) => {
+                    let _ = writeln!(wikitext, "| {}", thousands(size));
+                }
+                PollOption::NotAvailable => {
+                    let _ = writeln!(wikitext, "| {NOT_AVAILABLE}");
+                }
+            }
+
+            let values: Vec<Option<f32>> = parties
+                .iter()
+                .map(|party| result_value(poll.party_results.get(party)?))
+                .collect();
+            let mut sorted: Vec<f32> = values.iter().flatten().copied().collect();
+            sorted.sort_by(|a, b| b.total_cmp(a));
+            let leader = sorted.first().copied();
+            let leaders: Vec<usize> = (0..values.len())
+                .filter(|&i| values[i].is_some() && values[i] == leader)
+                .collect();
+            let lead_color = match leaders.as_slice() {
+                [leader] => colors[*leader],
+                _ => None,
+            };
+
+            for (i, value) in values.iter().enumerate() {
+                match value {
+                    Some(value) if leaders.contains(&i) => {
+                        let _ = writeln!(
+                            wikitext,
+                            "| {}'''{value}'''",
+                            shading(if leaders.len() == 1 { colors[i] } else { None })
+                        );
+                    }
+                    Some(value) => {
+                        let _ = writeln!(wikitext, "| {value}");
+                    }
+                    None => {
+                        let _ = writeln!(wikitext, "| {NOT_AVAILABLE}");
+                    }
+                }
+            }
+            match result_value(&poll.other) {
+                Some(other) => {
+                    let _ = writeln!(wikitext, "| {other}");
+                }
+                None => {
+                    let _ = writeln!(wikitext, "| {NOT_AVAILABLE}");
+                }
+            }
+            match (sorted.first(), sorted.get(1)) {
+                (Some(first), Some(second)) => {
+                    let lead = ((first - second) * 10.0).round() / 10.0;
+                    let _ = writeln!(wikitext, "| {}{lead}", shading(lead_color));
+                }
+                _ => {
+                    let _ = writeln!(wikitext, "| {NOT_AVAILABLE}");
+                }
+            }
+        }
+        wikitext.push_str("|}\n");
+        wikitext
+    }
+}
+
+fn result_value(result: &PollOption<PercentageOrSeats>) -> Option<f32> {
+    match result {
+        PollOption::Some(result) => Some(result.value()),
+        PollOption::NotAvailable => None,
+    }
+}
+
+/// Returns the cell attributes shading a cell in a party color, with legible text.
+fn shading(color: Option<&str>) -> String {
+    match color {
+        Some(color) => {
+            let text = match parse_color(color) {
+                Some(rgb) if is_dark(rgb) => "white",
+                _ => "black",
+            };
+            format!("style=\"background:{color}; color:{text};\" | ")
+        }
+        None => String::new(),
+    }
+}
+
+/// Formats fieldwork dates as Wikipedia does, such as "6–8 Mar 2024" or "28 Feb – 2 Mar 2024".
+fn fieldwork_dates(start: NaiveDate, end: NaiveDate) -> String {
+    if start == end {
+        end.format("%-d %b %Y").to_string()
+    } else if (start.year(), start.month()) == (end.year(), end.month()) {
+        format!("{}–{}", start.day(), end.format("%-d %b %Y"))
+    } else if start.year() == end.year() {
+        format!("{} – {}", start.format("%-d %b"), end.format("%-d %b %Y"))
+    } else {
+        format!(
+            "{} – {}",
+            start.format("%-d %b %Y"),
+            end.format("%-d %b %Y")
+        )
+    }
+}
+
+/// Formats a sample size with thousands separators, such as "1,204".
+fn thousands(size: f32) -> String {
+    let digits = (size.round() as u64).to_string();
+    let mut formatted = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ColumnAssignment {
+    #[serde(rename = "Column")]
+    column: String,
+    #[serde(rename = "Party", default)]
+    party: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Maps the party column headers of a Wikipedia table to Europe Elects party columns.
+///
+/// Headers without a mapping keep their name, so only parties named differently on Wikipedia need one.
+/// A header can also be ignored, such as a "Margin of error" column which is not a party.
+pub struct ColumnMapping {
+    columns: HashMap<String, Option<String>>,
+}
+
+impl ColumnMapping {
+    /// Creates an empty [ColumnMapping].
+    pub fn new() -> Self {
+        ColumnMapping::default()
+    }
+
+    /// Reads a Wikipedia column into a party column.
+    pub fn map(&mut self, column: &str, party: &str) {
+        self.columns
+            .insert(column.to_string(), Some(party.to_string()));
+    }
+
+    /// Leaves a Wikipedia column out of the imported polls.
+    pub fn ignore(&mut self, column: &str) {
+        self.columns.insert(column.to_string(), None);
+    }
+
+    /// As with [ColumnMapping::from_str], but reads the .csv data from a file.
+    pub fn try_from_path(path: &str) -> Result<ColumnMapping, ColumnMappingError> {
+        let s = std::fs::read_to_string(path)?;
+        ColumnMapping::from_str(&s)
+    }
+}
+
+impl FromStr for ColumnMapping {
+    type Err = ColumnMappingError;
+
+    /// Reads a column mapping from .csv data with "Column" and "Party" columns, where an empty party ignores the column.
+    /// ```
+    /// use europe_elects_csv::wikitext::ColumnMapping;
+    /// use std::str::FromStr;
+    /// let example = "Column,Party
+    /// Union,CDU/CSU
+    /// Margin of error,";
+    ///
+    /// assert!(ColumnMapping::from_str(example).is_ok());
+    /// ```
+    fn from_str(s: &str) -> Result<ColumnMapping, ColumnMappingError> {
+        let mut rdr = ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(s.as_bytes());
+        let mut mapping = ColumnMapping::new();
+
+        for result in rdr.deserialize() {
+            let record: ColumnAssignment = result?;
+            let party = record.party.filter(|party| !party.is_empty());
+            mapping.columns.insert(record.column, party);
+        }
+
+        Ok(mapping)
+    }
+}
+
+/// What a column of a Wikipedia table holds.
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Fieldwork,
+    PollingFirm,
+    Commissioner,
+    SampleSize,
+    Party(String),
+    Other,
+    Ignored,
+}
+
+impl Column {
+    fn classify(header: &str, mapping: &ColumnMapping) -> Column {
+        if let Some(party) = mapping.columns.get(header) {
+            return match party {
+                Some(party) => Column::Party(party.clone()),
+                None => Column::Ignored,
+            };
+        }
+        let lowercase = header.to_lowercase();
+        // Dates are matched as whole words, so that parties such as "Independent candidates" are not taken for them.
+        let words: Vec<&str> = lowercase
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if lowercase.is_empty() || lowercase == "lead" {
+            Column::Ignored
+        } else if words
+            .iter()
+            .any(|word| matches!(*word, "fieldwork" | "date" | "dates"))
+        {
+            Column::Fieldwork
+        } else if lowercase.contains("firm") || lowercase.contains("pollster") {
+            Column::PollingFirm
+        } else if lowercase.contains("commission") || lowercase.contains("client") {
+            Column::Commissioner
+        } else if lowercase.contains("sample") {
+            Column::SampleSize
+        } else if lowercase == "others" || lowercase == "other" {
+            Column::Other
+        } else {
+            Column::Party(header.to_string())
+        }
+    }
+}
+
+/// One cell of a table after its row and column spans are laid out.
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    text: String,
+    header: bool,
+    /// Whether the slot is covered by the cell to its left.
+    spanned: bool,
+}
+
+/// A cell as written in wikitext, before layout.
+struct Cell {
+    text: String,
+    header: bool,
+    rowspan: usize,
+    colspan: usize,
+}
+
+impl RawPollTable {
+    /// Reads the first wikitable in wikitext from a Wikipedia opinion polling page.
+    ///
+    /// Columns are recognized by their headers: the fieldwork date, the polling firm, the commissioner or client, the sample size and "Others".
+    /// "Lead" is skipped, and every other column is read as a party, named as in the [ColumnMapping].
+    /// Links, bold text, references and common templates are stripped from cells, and cells without a number are read as not available.
+    /// Rows whose polling firm cell spans several columns, as Wikipedia uses for election results, are skipped.
+    /// Polls are assumed to be national.
+    /// ```
+    /// use europe_elects_csv::*;
+    /// use europe_elects_csv::wikitext::{ColumnMapping, Wikitable};
+    /// let example = "Polling Firm,Commissioners,Fieldwork Start,Fieldwork End,Scope,Sample Size,Sample Size Qualification,Participation,Precision,CDU/CSU,SPD,Other
+    /// Epic Polling,Not Available,2024-03-06,2024-03-08,National,1204,Provided,Not Available,1%,30%,15.5%,54.5%";
+    /// let poll_table = PollTable::from_str(example, "de").unwrap();
+    /// let wikitext = Wikitable::new(&poll_table)
+    ///     .to_wikitext()
+    ///     .replace("! CDU/CSU", "! [[CDU/CSU|Union]]")
+    ///     .replace("! SPD", "! Independent candidates");
+    ///
+    /// let mut mapping = ColumnMapping::new();
+    /// mapping.map("Union", "CDU/CSU");
+    /// let raw_poll_table = RawPollTable::from_wikitext(&wikitext, &mapping).unwrap();
+    /// let poll = &raw_poll_table.polls()[0];
+    ///
+    /// assert_eq!(poll.fieldwork_midpoint(), chrono::NaiveDate::from_ymd_opt(2024, 3, 7).unwrap());
+    /// assert_eq!(poll.party_results()["CDU/CSU"].poll_unwrap().value(), 30.0);
+    /// assert_eq!(poll.party_results()["Independent candidates"].poll_unwrap().value(), 15.5);
+    /// ```
+    pub fn from_wikitext(s: &str, mapping: &ColumnMapping) -> Result<RawPollTable, WikitableError> {
+        let rows = layout(parse_cells(s)?);
+        let header_rows = rows
+            .iter()
+            .take_while(|row| !row.is_empty() && row.iter().all(|slot| slot.header))
+            .count();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut headers = vec![String::new(); width];
+        for row in &rows[..header_rows] {
+            for (i, slot) in row.iter().enumerate() {
+                if !slot.text.is_empty() && !slot.spanned {
+                    headers[i] = slot.text.clone();
+                }
+            }
+        }
+        let columns: Vec<Column> = headers
+            .iter()
+            .map(|header| Column::classify(header, mapping))
+            .collect();
+        let find = |column: Column, name: &'static str| {
+            columns
+                .iter()
+                .position(|c| *c == column)
+                .ok_or(WikitableError::MissingColumnError(name))
+        };
+        let fieldwork = find(Column::Fieldwork, "fieldwork date")?;
+        let polling_firm = find(Column::PollingFirm, "polling firm")?;
+
+        let mut polls = Vec::new();
+        for row in &rows[header_rows..] {
+            let text = |i: usize| row.get(i).map(|slot| slot.text.as_str()).unwrap_or("");
+            if row.iter().all(|slot| slot.header || slot.text.is_empty())
+                || row.get(polling_firm + 1).is_some_and(|slot| slot.spanned)
+            {
+                continue;
+            }
+            let (fieldwork_start, fieldwork_end) = parse_fieldwork(text(fieldwork))
+                .ok_or_else(|| WikitableError::InvalidDateError(text(fieldwork).to_string()))?;
+
+            let mut commissioners = PollOption::NotAvailable;
+            let mut sample_size = PollOption::NotAvailable;
+            let mut party_results = HashMap::new();
+            let mut other = PollOption::NotAvailable;
+            for (i, column) in columns.iter().enumerate() {
+                match column {
+                    Column::Commissioner => {
+                        commissioners = match text(i) {
+                            "" | "–" | "—" | "-" | "N/A" => PollOption::NotAvailable,
+                            commissioner => PollOption::Some(commissioner.to_string()),
+                        }
+                    }
+                    Column::SampleSize => sample_size = parse_sample_size(text(i)),
+                    Column::Party(party) => {
+                        party_results.insert(party.clone(), parse_share(text(i)));
+                    }
+                    Column::Other => other = parse_share(text(i)),
+                    Column::Fieldwork | Column::PollingFirm | Column::Ignored => {}
+                }
+            }
+
+            polls.push(Poll {
+                polling_firm: text(polling_firm).to_string(),
+                commissioners,
+                fieldwork_start,
+                fieldwork_end,
+                scope: Scope::National,
+                sample_size,
+                sample_size_qualification: PollOption::NotAvailable,
+                participation: PollOption::NotAvailable,
+                precision: PollOption::NotAvailable,
+                party_results,
+                other,
+            });
+        }
+
+        Ok(RawPollTable::new(polls))
+    }
+}
+
+/// Reads the rows of cells of the first wikitable in wikitext.
+fn parse_cells(s: &str) -> Result<Vec<Vec<Cell>>, WikitableError> {
+    let mut lines = s.lines().map(str::trim);
+    lines
+        .by_ref()
+        .find(|line| line.starts_with("{|"))
+        .ok_or(WikitableError::NoTableError)?;
+
+    let mut rows: Vec<Vec<Cell>> = vec![Vec::new()];
+    for line in lines {
+        if line.starts_with("|}") {
+            break;
+        } else if line.starts_with("|-") {
+            rows.push(Vec::new());
+        } else if line.starts_with("|+") {
+            continue;
+        } else if let Some(cells) = line.strip_prefix('!') {
+            let row = rows.last_mut().unwrap();
+            for cell in split_outside(cells, &["!!", "||"]) {
+                row.push(parse_cell(cell, true));
+            }
+        } else if let Some(cells) = line.strip_prefix('|') {
+            let row = rows.last_mut().unwrap();
+            for cell in split_outside(cells, &["||"]) {
+                row.push(parse_cell(cell, false));
+            }
+        } else if let Some(cell) = rows.last_mut().and_then(|row| row.last_mut()) {
+            // A line without a marker continues the previous cell.
+            let continued = plain_text(line);
+            if !continued.is_empty() {
+                cell.text = format!("{} {continued}", cell.text).trim().to_string();
+            }
+        }
+    }
+    rows.retain(|row| !row.is_empty());
+    Ok(rows)
+}
+
+/// Splits a line of cells at separators, except inside links and templates.
+fn split_outside<'s>(s: &'s str, separators: &[&str]) -> Vec<&'s str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        if rest.starts_with("[[") || rest.starts_with("{{") {
+            depth += 1;
+            i += 2;
+        } else if rest.starts_with("]]") || rest.starts_with("}}") {
+            depth = depth.saturating_sub(1);
+            i += 2;
+        } else if let Some(separator) = separators
+            .iter()
+            .find(|separator| depth == 0 && rest.starts_with(**separator))
+        {
+            parts.push(&s[start..i]);
+            i += separator.len();
+            start = i;
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Parses a cell written as `content` or `attributes | content`.
+fn parse_cell(cell: &str, header: bool) -> Cell {
+    let (attributes, content) = match split_outside(cell, &["|"]).as_slice() {
+        [attributes, content] if attributes.contains('=') || attributes.trim().is_empty() => {
+            (*attributes, *content)
+        }
+        _ => ("", cell),
+    };
+    Cell {
+        text: plain_text(content),
+        header,
+        rowspan: span(attributes, "rowspan"),
+        colspan: span(attributes, "colspan"),
+    }
+}
+
+/// Reads a `rowspan` or `colspan` attribute, which is 1 when absent.
+fn span(attributes: &str, name: &str) -> usize {
+    let Some(start) = attributes.find(name) else {
+        return 1;
+    };
+    attributes[start + name.len()..]
+        .trim_start_matches(['=', '"', '\'', ' '])
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Fills the slots of cells spanning into this row from earlier rows, up to the next free column.
+fn take_pending(slots: &mut Vec<Slot>, pending: &mut [Option<(usize, Slot)>]) {
+    while let Some(Some((remaining, slot))) = pending.get_mut(slots.len()) {
+        slots.push(slot.clone());
+        *remaining -= 1;
+        if *remaining == 0 {
+            pending[slots.len() - 1] = None;
+        }
+    }
+}
+
+/// Lays cells out on a grid, repeating cells spanning several rows into each of them.
+fn layout(rows: Vec<Vec<Cell>>) -> Vec<Vec<Slot>> {
+    // The slot continuing a cell from an earlier row, and for how many more rows, per column.
+    let mut pending: Vec<Option<(usize, Slot)>> = Vec::new();
+    let mut grid = Vec::new();
+    for row in rows {
+        let mut slots: Vec<Slot> = Vec::new();
+        for cell in row {
+            take_pending(&mut slots, &mut pending);
+            for k in 0..cell.colspan {
+                let slot = Slot {
+                    text: if k == 0 {
+                        cell.text.clone()
+                    } else {
+                        String::new()
+                    },
+                    header: cell.header,
+                    spanned: k > 0,
+                };
+                if cell.rowspan > 1 {
+                    if pending.len() <= slots.len() {
+                        pending.resize(slots.len() + 1, None);
+                    }
+                    pending[slots.len()] = Some((cell.rowspan - 1, slot.clone()));
+                }
+                slots.push(slot);
+            }
+        }
+        take_pending(&mut slots, &mut pending);
+        grid.push(slots);
+    }
+    grid
+}
+
+/// Reduces wikitext markup to plain text, keeping the labels of links and the contents of formatting templates.
+fn plain_text(s: &str) -> String {
+    let mut text = s.to_string();
+
+    // References, then HTML tags. A "<" not starting a tag, as in "<1", is kept.
+    while let Some(start) = text.find("<ref") {
+        let Some(tag_end) = text[start..].find('>').map(|end| start + end + 1) else {
+            text.truncate(start);
+            break;
+        };
+        let end = if text[..tag_end].ends_with("/>") {
+            tag_end
+        } else {
+            text[tag_end..]
+                .find("</ref>")
+                .map_or(text.len(), |end| tag_end + end + "</ref>".len())
+        };
+        text.replace_range(start..end, "");
+    }
+    let mut from = 0;
+    while let Some(start) = text[from..].find('<').map(|start| from + start) {
+        let is_tag = text[start + 1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/');
+        match text[start..].find('>') {
+            Some(end) if is_tag => text.replace_range(start..start + end + 1, " "),
+            _ => from = start + 1,
+        }
+    }
+
+    // Templates, innermost first.
+    while let Some(start) = text.rfind("{{") {
+        let Some(close) = text[start..].find("}}").map(|close| start + close) else {
+            text.truncate(start);
+            break;
+        };
+        let inner = text[start + 2..close].to_string();
+        let arguments: Vec<&str> = inner.split('|').collect();
+        let replacement = match arguments[0].trim().to_lowercase().as_str() {
+            "nowrap" | "nobr" | "small" | "big" | "sort" | "hs" | "ntsh" => {
+                arguments[arguments.len() - 1].to_string()
+            }
+            "ndash" | "snd" => "–".to_string(),
+            _ => String::new(),
+        };
+        text.replace_range(start..close + 2, &replacement);
+    }
+
+    // Internal and external links.
+    while let Some(start) = text.find("[[") {
+        let Some(close) = text[start..].find("]]").map(|close| start + close) else {
+            break;
+        };
+        let label = text[start + 2..close]
+            .rsplit('|')
+            .next()
+            .unwrap_or("")
+            .to_string();
+        text.replace_range(start..close + 2, &label);
+    }
+    while let Some(start) = text.find("[http") {
+        let Some(close) = text[start..].find(']').map(|close| start + close) else {
+            break;
+        };
+        let label = text[start + 1..close]
+            .split_once(' ')
+            .map_or("", |(_, label)| label)
+            .to_string();
+        text.replace_range(start..close + 1, &label);
+    }
+
+    let text = text
+        .replace("'''", "")
+        .replace("''", "")
+        .replace("&nbsp;", " ")
+        .replace("&ndash;", "–")
+        .replace("&mdash;", "—");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_sample_size(text: &str) -> PollOption<f32> {
+    let digits: String = text
+        .chars()
+        .filter(|c| !matches!(c, ',' | ' ' | '\u{a0}' | '\u{2009}'))
+        .collect();
+    match digits.parse() {
+        Ok(size) => PollOption::Some(size),
+        Err(_) => PollOption::NotAvailable,
+    }
+}
+
+fn parse_share(text: &str) -> PollOption<PercentageOrSeats> {
+    let number = text.trim_end_matches('%').trim();
+    let share = number.parse().or_else(|_| number.replace(',', ".").parse());
+    match share {
+        Ok(share) => PollOption::Some(PercentageOrSeats::Percentage(Percentage(share))),
+        Err(_) => PollOption::NotAvailable,
+    }
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    ["%d %b %Y", "%d %B %Y", "%Y-%m-%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+}
+
+/// Parses fieldwork dates such as "8 Mar 2024", "6–8 Mar 2024", "28 Feb – 2 Mar 2024" or "28 Dec 2023 – 2 Jan 2024".
+fn parse_fieldwork(text: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let text = text.replace('—', "–");
+    if let Some(date) = parse_date(&text) {
+        return Some((date, date));
+    }
+    let (start, end) = ["–", " - ", "-"]
+        .iter()
+        .find_map(|separator| text.split_once(separator))?;
+    let end = parse_date(end.trim())?;
+    let start = start.trim();
+
+    let start = parse_date(start)
+        .or_else(|| {
+            // The start shares the end's year, unless that puts it after the end.
+            [end.year(), end.year() - 1]
+                .iter()
+                .filter_map(|year| parse_date(&format!("{start} {year}")))
+                .find(|date| *date <= end)
+        })
+        .or_else(|| NaiveDate::from_ymd_opt(end.year(), end.month(), start.parse().ok()?))?;
+    Some((start, end))
+}